@@ -0,0 +1,76 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use force_it_v2_rust::clock::ManualClock;
+use force_it_v2_rust::emitter::{EmissionRate, Emitter, EmitterConfig, EmitterShape, ValueRange};
+use force_it_v2_rust::parallel::Execution;
+use force_it_v2_rust::physics::{PhysicsConfig, PhysicsSystem, WallModel};
+use force_it_v2_rust::vector::Vec3;
+use force_it_v2_rust::wall_grid::WallGrid;
+use std::sync::Arc;
+
+const FORCE_PARTICLES: usize = 100_000;
+const THREAD_COUNTS: [usize; 5] = [1, 2, 4, 8, 16];
+
+/// A 100×50 wall with a cloud of force particles heading into it
+fn loaded_system(wall_model: WallModel, execution: Execution) -> PhysicsSystem {
+    let config = PhysicsConfig {
+        wall_model,
+        execution,
+        wall_grid: WallGrid {
+            columns: 100,
+            rows: 50,
+            ..WallGrid::default()
+        },
+        particle_budget: FORCE_PARTICLES,
+        seed: Some(1),
+        ..PhysicsConfig::default()
+    };
+    let mut system = PhysicsSystem::with_clock(config, Arc::new(ManualClock::new()));
+
+    let mut emitter = Emitter::new(EmitterConfig {
+        shape: EmitterShape::Sphere { radius: 200.0 },
+        rate: EmissionRate::Burst {
+            count: FORCE_PARTICLES,
+            interval: f64::INFINITY,
+        },
+        velocity_jitter: 20.0,
+        lifetime: ValueRange::constant(1.0e6),
+        ..EmitterConfig::default()
+    });
+    emitter.set_source(Vec3::from_coords(300.0, 50.0, -250.0), Vec3::from_coords(0.0, 0.0, 40.0));
+    let id = system.add_emitter(emitter);
+    system.step(system.config.fixed_dt);
+    system.emitter_mut(id).unwrap().enabled = false;
+    system
+}
+
+fn bench_step(c: &mut Criterion) {
+    for (name, wall_model) in [("module", WallModel::ModuleGravity), ("cloth", WallModel::Cloth)] {
+        let mut group = c.benchmark_group(format!("step/{}", name));
+        group.sample_size(20);
+
+        group.bench_function("single-threaded", |b| {
+            b.iter_batched(
+                || loaded_system(wall_model, Execution::SingleThreaded),
+                |mut system| system.step(system.config.fixed_dt),
+                BatchSize::LargeInput,
+            )
+        });
+
+        for threads in THREAD_COUNTS {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            group.bench_with_input(BenchmarkId::new("parallel", threads), &threads, |b, _| {
+                pool.install(|| {
+                    b.iter_batched(
+                        || loaded_system(wall_model, Execution::Parallel),
+                        |mut system| system.step(system.config.fixed_dt),
+                        BatchSize::LargeInput,
+                    )
+                })
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_step);
+criterion_main!(benches);
@@ -0,0 +1,237 @@
+use crate::emitter::EmitterId;
+use crate::vector::Vec3;
+
+/// Red, green, blue and alpha, each in `0.0..=1.0`
+pub type Rgba = [f32; 4];
+
+pub const WHITE: Rgba = [1.0, 1.0, 1.0, 1.0];
+
+/// Per-particle state that does not affect the motion, for rendering and game rules
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleAttributes {
+    pub color: Rgba,
+    /// Fraction of the lifetime used up: 0 at spawn, 1 at expiry. Particles that live
+    /// forever, like the wall, stay at 0.
+    pub age: f64,
+    pub temperature: f64,
+    pub charge: f64,
+    /// Emitter that spawned the particle; `None` for wall particles
+    pub emitter: Option<EmitterId>,
+    /// Free for the application to use
+    pub user_data: u64,
+}
+
+impl Default for ParticleAttributes {
+    fn default() -> Self {
+        Self {
+            color: WHITE,
+            age: 0.0,
+            temperature: 0.0,
+            charge: 0.0,
+            emitter: None,
+            user_data: 0,
+        }
+    }
+}
+
+/// Which population a particle belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParticleKind {
+    Wall,
+    Force,
+}
+
+/// What an attribute rule can see of a particle besides its attributes
+#[derive(Debug, Clone, Copy)]
+pub struct ParticleState<'a> {
+    pub kind: ParticleKind,
+    pub position: &'a Vec3,
+    pub velocity: &'a Vec3,
+    /// Total normal impulse from force particle hits during the step. Only tracked for
+    /// wall particles; always zero for force particles.
+    pub impulse: f64,
+}
+
+/// Updates particle attributes once per step, after the motion
+pub trait AttributeRule: Send + Sync {
+    fn update(&self, attributes: &mut ParticleAttributes, particle: &ParticleState, dt: f64);
+
+    /// Particles the rule runs on; all of them unless overridden
+    fn applies_to(&self, _kind: ParticleKind) -> bool {
+        true
+    }
+}
+
+/// Blends force particle color from `from` at spawn to `to` at expiry
+#[derive(Debug, Clone, PartialEq)]
+pub struct FadeWithAge {
+    pub from: Rgba,
+    pub to: Rgba,
+}
+
+impl FadeWithAge {
+    pub fn new(from: Rgba, to: Rgba) -> Self {
+        Self { from, to }
+    }
+}
+
+impl AttributeRule for FadeWithAge {
+    fn update(&self, attributes: &mut ParticleAttributes, _particle: &ParticleState, _dt: f64) {
+        let t = attributes.age.clamp(0.0, 1.0) as f32;
+        for channel in 0..4 {
+            attributes.color[channel] = self.from[channel] + (self.to[channel] - self.from[channel]) * t;
+        }
+    }
+
+    fn applies_to(&self, kind: ParticleKind) -> bool {
+        kind == ParticleKind::Force
+    }
+}
+
+/// Heats wall particles by the impulse they take and lets them cool back to `ambient`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImpactHeating {
+    /// Temperature gained per unit of impulse
+    pub per_impulse: f64,
+    /// Rate the temperature relaxes towards `ambient`, in 1/s
+    pub cooling: f64,
+    pub ambient: f64,
+}
+
+impl ImpactHeating {
+    pub fn new(per_impulse: f64, cooling: f64) -> Self {
+        Self {
+            per_impulse,
+            cooling,
+            ambient: 0.0,
+        }
+    }
+}
+
+impl AttributeRule for ImpactHeating {
+    fn update(&self, attributes: &mut ParticleAttributes, particle: &ParticleState, dt: f64) {
+        attributes.temperature += particle.impulse * self.per_impulse;
+        // Exact exponential decay, so large steps cannot overshoot the ambient temperature
+        let excess = attributes.temperature - self.ambient;
+        attributes.temperature = self.ambient + excess * (-self.cooling * dt).exp();
+    }
+
+    fn applies_to(&self, kind: ParticleKind) -> bool {
+        kind == ParticleKind::Wall
+    }
+}
+
+/// Tints particles from `cold` to `hot` as their temperature rises from `0` to `max`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemperatureColor {
+    pub cold: Rgba,
+    pub hot: Rgba,
+    pub max: f64,
+}
+
+impl AttributeRule for TemperatureColor {
+    fn update(&self, attributes: &mut ParticleAttributes, _particle: &ParticleState, _dt: f64) {
+        let t = if self.max > 0.0 {
+            (attributes.temperature / self.max).clamp(0.0, 1.0) as f32
+        } else {
+            0.0
+        };
+        for channel in 0..4 {
+            attributes.color[channel] = self.cold[channel] + (self.hot[channel] - self.cold[channel]) * t;
+        }
+    }
+
+    fn applies_to(&self, kind: ParticleKind) -> bool {
+        kind == ParticleKind::Wall
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(kind: ParticleKind, impulse: f64) -> ParticleState<'static> {
+        const ORIGIN: Vec3 = Vec3 { x: 0.0, y: 0.0, z: 0.0 };
+        ParticleState {
+            kind,
+            position: &ORIGIN,
+            velocity: &ORIGIN,
+            impulse,
+        }
+    }
+
+    #[test]
+    fn fade_with_age_spans_from_spawn_to_expiry() {
+        let fade = FadeWithAge::new([1.0, 0.75, 0.5, 1.0], [0.0, 0.25, 0.0, 0.0]);
+        let mut attributes = ParticleAttributes::default();
+        let particle = state(ParticleKind::Force, 0.0);
+
+        fade.update(&mut attributes, &particle, 0.1);
+        assert_eq!(attributes.color, [1.0, 0.75, 0.5, 1.0]);
+
+        attributes.age = 1.0;
+        fade.update(&mut attributes, &particle, 0.1);
+        assert_eq!(attributes.color, [0.0, 0.25, 0.0, 0.0]);
+
+        attributes.age = 0.5;
+        fade.update(&mut attributes, &particle, 0.1);
+        assert_eq!(attributes.color, [0.5, 0.5, 0.25, 0.5]);
+    }
+
+    #[test]
+    fn impact_heating_cools_towards_ambient() {
+        let heating = ImpactHeating {
+            ambient: 20.0,
+            ..ImpactHeating::new(0.5, 2.0)
+        };
+        let mut attributes = ParticleAttributes {
+            temperature: 20.0,
+            ..ParticleAttributes::default()
+        };
+
+        heating.update(&mut attributes, &state(ParticleKind::Wall, 100.0), 0.0);
+        assert_eq!(attributes.temperature, 70.0);
+
+        let mut previous = attributes.temperature;
+        for _ in 0..60 {
+            heating.update(&mut attributes, &state(ParticleKind::Wall, 0.0), 1.0 / 60.0);
+            assert!(attributes.temperature < previous && attributes.temperature > 20.0);
+            previous = attributes.temperature;
+        }
+        // One second at a cooling rate of 2/s leaves e⁻² of the excess
+        let expected = 20.0 + 50.0 * (-2.0f64).exp();
+        assert!((attributes.temperature - expected).abs() < 1e-9, "{}", attributes.temperature);
+    }
+
+    #[test]
+    fn temperature_color_clamps_at_both_ends() {
+        let tint = TemperatureColor {
+            cold: WHITE,
+            hot: [1.0, 0.0, 0.0, 1.0],
+            max: 10.0,
+        };
+        let particle = state(ParticleKind::Wall, 0.0);
+        let mut attributes = ParticleAttributes {
+            temperature: -5.0,
+            ..ParticleAttributes::default()
+        };
+        tint.update(&mut attributes, &particle, 0.1);
+        assert_eq!(attributes.color, WHITE);
+
+        attributes.temperature = 5.0;
+        tint.update(&mut attributes, &particle, 0.1);
+        assert_eq!(attributes.color, [1.0, 0.5, 0.5, 1.0]);
+
+        attributes.temperature = 50.0;
+        tint.update(&mut attributes, &particle, 0.1);
+        assert_eq!(attributes.color, [1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn built_in_rules_pick_their_particles() {
+        assert!(FadeWithAge::new(WHITE, WHITE).applies_to(ParticleKind::Force));
+        assert!(!FadeWithAge::new(WHITE, WHITE).applies_to(ParticleKind::Wall));
+        assert!(ImpactHeating::new(1.0, 1.0).applies_to(ParticleKind::Wall));
+        assert!(!ImpactHeating::new(1.0, 1.0).applies_to(ParticleKind::Force));
+    }
+}
@@ -0,0 +1,211 @@
+use crate::collider::{Capsule, Collider, Contact};
+use crate::input::{BodyJoints, Joint};
+use crate::material::{MaterialId, WALL_MATERIAL};
+use crate::vector::Vec3;
+use std::time::Duration;
+
+/// Capsule that moves with the body; its surface velocity blends the velocities of its ends
+#[derive(Debug, Clone, PartialEq)]
+pub struct KinematicCapsule {
+    pub capsule: Capsule,
+    pub velocity_a: Vec3,
+    pub velocity_b: Vec3,
+}
+
+impl Collider for KinematicCapsule {
+    fn contact(&self, center: &Vec3, radius: f64) -> Option<Contact> {
+        self.capsule.contact(center, radius)
+    }
+
+    fn velocity_at(&self, point: &Vec3) -> Vec3 {
+        let t = self.capsule.segment_parameter(point);
+        self.velocity_a * (1.0 - t) + self.velocity_b * t
+    }
+}
+
+/// Limb or trunk segment between two joints
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BodySegment {
+    pub from: Joint,
+    pub to: Joint,
+    pub radius: f64,
+}
+
+impl BodySegment {
+    pub fn new(from: Joint, to: Joint, radius: f64) -> Self {
+        Self { from, to, radius }
+    }
+
+    /// Segments ending in a hand only count while that hand is tracked
+    fn is_tracked(&self, body: &BodyJoints) -> bool {
+        [self.from, self.to]
+            .into_iter()
+            .all(|joint| body.hand(joint).is_none_or(|hand| hand.is_tracked))
+    }
+}
+
+/// The tracked body as a set of kinematic capsules that particles bounce off
+#[derive(Debug, Clone)]
+pub struct BodyColliders {
+    pub segments: Vec<BodySegment>,
+    pub material: MaterialId,
+    /// Also push force particles around. Off by default: the hand emitters spawn inside the
+    /// forearm capsules, so the body would otherwise swallow its own emission.
+    pub collide_force_particles: bool,
+    pub enabled: bool,
+    capsules: Vec<KinematicCapsule>,
+    previous: Option<(Duration, Vec<(Vec3, Vec3)>)>,
+}
+
+impl BodyColliders {
+    pub fn new(segments: Vec<BodySegment>) -> Self {
+        Self {
+            segments,
+            material: WALL_MATERIAL,
+            collide_force_particles: false,
+            // Stays off until `update` has placed it on a body
+            enabled: false,
+            capsules: Vec::new(),
+            previous: None,
+        }
+    }
+
+    /// Rebuild the capsules from the current pose at time `now`.
+    /// End velocities are the joint motion since the previous update.
+    pub fn update(&mut self, body: &BodyJoints, now: Duration) {
+        let ends: Vec<(Vec3, Vec3)> = self
+            .segments
+            .iter()
+            .map(|segment| (body.position(segment.from), body.position(segment.to)))
+            .collect();
+
+        let dt = match &self.previous {
+            Some((previous_time, previous_ends)) if previous_ends.len() == ends.len() => {
+                now.saturating_sub(*previous_time).as_secs_f64()
+            }
+            _ => 0.0,
+        };
+
+        self.capsules.clear();
+        for (index, segment) in self.segments.iter().enumerate() {
+            if !segment.is_tracked(body) {
+                continue;
+            }
+            let (a, b) = ends[index];
+            let (velocity_a, velocity_b) = match &self.previous {
+                Some((_, previous_ends)) if dt > 0.0 => {
+                    let (previous_a, previous_b) = previous_ends[index];
+                    ((a - previous_a) * (1.0 / dt), (b - previous_b) * (1.0 / dt))
+                }
+                _ => (Vec3::new(), Vec3::new()),
+            };
+            self.capsules.push(KinematicCapsule {
+                capsule: Capsule::new(a, b, segment.radius),
+                velocity_a,
+                velocity_b,
+            });
+        }
+
+        self.previous = Some((now, ends));
+        self.enabled = true;
+    }
+
+    /// Capsules of the tracked segments as of the last `update`
+    pub fn capsules(&self) -> &[KinematicCapsule] {
+        &self.capsules
+    }
+}
+
+impl Default for BodyColliders {
+    /// Forearms, upper arms and torso
+    fn default() -> Self {
+        Self::new(vec![
+            BodySegment::new(Joint::ElbowRight, Joint::RightHand, 6.0),
+            BodySegment::new(Joint::ElbowLeft, Joint::LeftHand, 6.0),
+            BodySegment::new(Joint::ShoulderRight, Joint::ElbowRight, 8.0),
+            BodySegment::new(Joint::ShoulderLeft, Joint::ElbowLeft, 8.0),
+            BodySegment::new(Joint::SpineShoulder, Joint::SpineBase, 25.0),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracked_body() -> BodyJoints {
+        let mut body = BodyJoints::default();
+        body.right_hand.is_tracked = true;
+        body.left_hand.is_tracked = true;
+        body.right_hand.position = Vec3::from_coords(40.0, 10.0, -30.0);
+        body.left_hand.position = Vec3::from_coords(-40.0, 10.0, -30.0);
+        body.elbow_right = Vec3::from_coords(35.0, -20.0, -20.0);
+        body.elbow_left = Vec3::from_coords(-35.0, -20.0, -20.0);
+        body.shoulder_right = Vec3::from_coords(20.0, -40.0, 0.0);
+        body.shoulder_left = Vec3::from_coords(-20.0, -40.0, 0.0);
+        body.spine_shoulder = Vec3::from_coords(0.0, -40.0, 0.0);
+        body.spine_base = Vec3::from_coords(0.0, -100.0, 0.0);
+        body
+    }
+
+    #[test]
+    fn default_segments_follow_the_joints() {
+        let body = tracked_body();
+        let mut colliders = BodyColliders::default();
+        assert!(!colliders.enabled);
+        colliders.update(&body, Duration::ZERO);
+        assert!(colliders.enabled);
+
+        let capsules = colliders.capsules();
+        assert_eq!(capsules.len(), 5);
+        for (capsule, segment) in capsules.iter().zip(&colliders.segments) {
+            assert_eq!(capsule.capsule.a, body.position(segment.from));
+            assert_eq!(capsule.capsule.b, body.position(segment.to));
+            assert_eq!(capsule.capsule.radius, segment.radius);
+        }
+        // The torso is the widest part
+        assert_eq!(capsules[4].capsule.radius, 25.0);
+    }
+
+    #[test]
+    fn untracked_hands_drop_their_forearms() {
+        let mut body = tracked_body();
+        body.right_hand.is_tracked = false;
+        let mut colliders = BodyColliders::default();
+        colliders.update(&body, Duration::ZERO);
+        let ends: Vec<Vec3> = colliders.capsules().iter().map(|capsule| capsule.capsule.b).collect();
+        assert_eq!(ends.len(), 4);
+        assert!(!ends.contains(&body.right_hand.position));
+        assert!(ends.contains(&body.left_hand.position));
+
+        body.left_hand.is_tracked = false;
+        colliders.update(&body, Duration::from_millis(16));
+        assert_eq!(colliders.capsules().len(), 3);
+    }
+
+    #[test]
+    fn capsule_velocity_comes_from_joint_motion() {
+        let mut body = tracked_body();
+        let mut colliders = BodyColliders::new(vec![BodySegment::new(Joint::ElbowRight, Joint::RightHand, 6.0)]);
+        colliders.update(&body, Duration::from_millis(100));
+        // Nothing to difference against yet
+        assert_eq!(colliders.capsules()[0].velocity_a, Vec3::new());
+
+        body.elbow_right.x += 5.0;
+        body.right_hand.position.z += 10.0;
+        colliders.update(&body, Duration::from_millis(150));
+        let capsule = &colliders.capsules()[0];
+        assert_eq!(capsule.velocity_a, Vec3::from_coords(100.0, 0.0, 0.0));
+        assert_eq!(capsule.velocity_b, Vec3::from_coords(0.0, 0.0, 200.0));
+
+        // The surface velocity blends the ends along the segment
+        let midpoint = (capsule.capsule.a + capsule.capsule.b) * 0.5;
+        let velocity = capsule.velocity_at(&midpoint);
+        assert!((velocity - Vec3::from_coords(50.0, 0.0, 100.0)).magnitude() < 1e-9);
+        assert_eq!(capsule.velocity_at(&capsule.capsule.b), capsule.velocity_b);
+
+        // No time passed: no velocity rather than a division by zero
+        colliders.update(&body, Duration::from_millis(150));
+        assert_eq!(colliders.capsules()[0].velocity_b, Vec3::new());
+    }
+}
@@ -0,0 +1,289 @@
+use crate::particle::Particle;
+use crate::vector::Vec3;
+
+/// Coordinate axis a boundary limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    pub fn get(self, v: &Vec3) -> f64 {
+        match self {
+            Axis::X => v.x,
+            Axis::Y => v.y,
+            Axis::Z => v.z,
+        }
+    }
+
+    pub fn set(self, v: &mut Vec3, value: f64) {
+        match self {
+            Axis::X => v.x = value,
+            Axis::Y => v.y = value,
+            Axis::Z => v.z = value,
+        }
+    }
+}
+
+/// What happens to a particle that crosses a boundary
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BoundaryMode {
+    /// Mirror back inside, reversing the outward velocity scaled by `restitution`
+    Reflect { restitution: f64 },
+    /// Stop at the limit, losing the outward velocity
+    Clamp,
+    /// Re-enter from the opposite limit. Needs both limits; acts as `Clamp` otherwise.
+    Wrap,
+    /// Remove the particle. Wall particles cannot leave the grid, so they snap back to rest.
+    Kill,
+    /// Glide back to the rest position over `duration` seconds.
+    /// Force particles have no rest position and are removed instead.
+    Reset { duration: f64 },
+}
+
+/// Limits on one axis; either side may be open
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AxisBoundary {
+    pub axis: Axis,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub mode: BoundaryMode,
+}
+
+/// A boundary a particle crossed that takes it out of normal simulation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Escape {
+    Kill,
+    Reset { duration: f64 },
+}
+
+impl AxisBoundary {
+    /// Plane keeping particles at or above `min`
+    pub fn at_least(axis: Axis, min: f64, mode: BoundaryMode) -> Self {
+        Self {
+            axis,
+            min: Some(min),
+            max: None,
+            mode,
+        }
+    }
+
+    /// Plane keeping particles at or below `max`
+    pub fn at_most(axis: Axis, max: f64, mode: BoundaryMode) -> Self {
+        Self {
+            axis,
+            min: None,
+            max: Some(max),
+            mode,
+        }
+    }
+
+    /// Slab keeping particles between `min` and `max`
+    pub fn between(axis: Axis, min: f64, max: f64, mode: BoundaryMode) -> Self {
+        Self {
+            axis,
+            min: Some(min),
+            max: Some(max),
+            mode,
+        }
+    }
+
+    /// Box from `min` to `max` with the same mode on every face
+    pub fn aabb(min: Vec3, max: Vec3, mode: BoundaryMode) -> Vec<Self> {
+        [Axis::X, Axis::Y, Axis::Z]
+            .into_iter()
+            .map(|axis| Self::between(axis, axis.get(&min), axis.get(&max), mode))
+            .collect()
+    }
+
+    pub fn is_outside(&self, position: &Vec3) -> bool {
+        let value = self.axis.get(position);
+        self.min.is_some_and(|min| value < min) || self.max.is_some_and(|max| value > max)
+    }
+
+    /// Bring a particle that has crossed back in bounds, or report that it escaped
+    pub fn apply(&self, position: &mut Vec3, velocity: &mut Vec3) -> Option<Escape> {
+        if !self.is_outside(position) {
+            return None;
+        }
+        let value = self.axis.get(position);
+        let speed = self.axis.get(velocity);
+        let below = self.min.is_some_and(|min| value < min);
+        let limit = if below { self.min } else { self.max }.unwrap_or(value);
+        // Velocity pointing further out of bounds
+        let outward = if below { speed < 0.0 } else { speed > 0.0 };
+
+        let clamp = |position: &mut Vec3, velocity: &mut Vec3| {
+            self.axis.set(position, limit);
+            if outward {
+                self.axis.set(velocity, 0.0);
+            }
+        };
+
+        match self.mode {
+            BoundaryMode::Reflect { restitution } => {
+                // An overshoot wider than a slab would mirror past the far side; stop there
+                let mirrored = 2.0 * limit - value;
+                let mirrored = self.max.map_or(mirrored, |max| mirrored.min(max));
+                let mirrored = self.min.map_or(mirrored, |min| mirrored.max(min));
+                self.axis.set(position, mirrored);
+                if outward {
+                    self.axis.set(velocity, -speed * restitution);
+                }
+            }
+            BoundaryMode::Clamp => clamp(position, velocity),
+            BoundaryMode::Wrap => match (self.min, self.max) {
+                (Some(min), Some(max)) if max > min => {
+                    self.axis.set(position, min + (value - min).rem_euclid(max - min));
+                }
+                _ => clamp(position, velocity),
+            },
+            BoundaryMode::Kill => return Some(Escape::Kill),
+            BoundaryMode::Reset { duration } => return Some(Escape::Reset { duration }),
+        }
+        None
+    }
+
+    /// Whether this boundary removes particles rather than moving them
+    pub fn removes(&self) -> bool {
+        matches!(self.mode, BoundaryMode::Kill | BoundaryMode::Reset { .. })
+    }
+}
+
+/// Apply `boundaries` in order, stopping at the first one the particle escapes through
+pub fn apply_boundaries(boundaries: &[AxisBoundary], position: &mut Vec3, velocity: &mut Vec3) -> Option<Escape> {
+    boundaries
+        .iter()
+        .find_map(|boundary| boundary.apply(position, velocity))
+}
+
+/// A wall particle gliding back to rest after crossing a `Reset` boundary
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResetAnimation {
+    from: Vec3,
+    elapsed: f64,
+    duration: f64,
+}
+
+impl ResetAnimation {
+    pub fn new(from: Vec3, duration: f64) -> Self {
+        Self {
+            from,
+            elapsed: 0.0,
+            duration,
+        }
+    }
+
+    /// Move `particle` `dt` seconds further along the way back; returns true once it is at rest
+    pub fn advance(&mut self, dt: f64, particle: &mut Particle) -> bool {
+        self.elapsed += dt;
+        if self.elapsed >= self.duration {
+            particle.reset_to_original();
+            return true;
+        }
+
+        // Smoothstep, so the particle eases out and settles in without a jolt
+        let t = self.elapsed / self.duration;
+        let eased = t * t * (3.0 - 2.0 * t);
+        let rest = *particle.get_original_position();
+        particle.position = self.from + (rest - self.from) * eased;
+        particle.velocity = Vec3::new();
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Position and velocity along X after applying `boundary` at `x` moving at `vx`
+    fn apply(boundary: &AxisBoundary, x: f64, vx: f64) -> (f64, f64, Option<Escape>) {
+        let mut position = Vec3::from_coords(x, 1.0, 2.0);
+        let mut velocity = Vec3::from_coords(vx, 3.0, 4.0);
+        let escape = boundary.apply(&mut position, &mut velocity);
+        // Other axes are never touched
+        assert_eq!((position.y, position.z, velocity.y, velocity.z), (1.0, 2.0, 3.0, 4.0));
+        (position.x, velocity.x, escape)
+    }
+
+    #[test]
+    fn particles_inside_are_left_alone() {
+        let boundary = AxisBoundary::between(Axis::X, 0.0, 10.0, BoundaryMode::Kill);
+        assert_eq!(apply(&boundary, 0.0, -5.0), (0.0, -5.0, None));
+        assert_eq!(apply(&boundary, 10.0, 5.0), (10.0, 5.0, None));
+    }
+
+    #[test]
+    fn reflect_mirrors_and_reverses_outward_velocity() {
+        let reflect = BoundaryMode::Reflect { restitution: 0.5 };
+        let slab = AxisBoundary::between(Axis::X, 0.0, 10.0, reflect);
+        assert_eq!(apply(&slab, -2.0, -8.0), (2.0, 4.0, None));
+        assert_eq!(apply(&slab, 13.0, 8.0), (7.0, -4.0, None));
+        // Already heading back in: the velocity is kept
+        assert_eq!(apply(&slab, 12.0, -3.0), (8.0, -3.0, None));
+
+        // Overshooting by more than the slab is wide stops at the far side
+        assert_eq!(apply(&slab, -25.0, -8.0), (10.0, 4.0, None));
+        assert_eq!(apply(&slab, 31.0, 8.0), (0.0, -4.0, None));
+        let (x, _, _) = apply(&slab, -25.0, -8.0);
+        assert!(!slab.is_outside(&Vec3::from_coords(x, 0.0, 0.0)));
+
+        let floor = AxisBoundary::at_least(Axis::X, 0.0, reflect);
+        assert_eq!(apply(&floor, -25.0, -8.0), (25.0, 4.0, None));
+    }
+
+    #[test]
+    fn clamp_stops_at_the_limit() {
+        let ceiling = AxisBoundary::at_most(Axis::X, 10.0, BoundaryMode::Clamp);
+        assert_eq!(apply(&ceiling, 14.0, 6.0), (10.0, 0.0, None));
+        assert_eq!(apply(&ceiling, 14.0, -6.0), (10.0, -6.0, None));
+        assert_eq!(apply(&ceiling, -1000.0, -6.0), (-1000.0, -6.0, None));
+    }
+
+    #[test]
+    fn wrap_reenters_from_the_opposite_side() {
+        let slab = AxisBoundary::between(Axis::X, 0.0, 10.0, BoundaryMode::Wrap);
+        assert_eq!(apply(&slab, 12.0, 6.0), (2.0, 6.0, None));
+        assert_eq!(apply(&slab, -3.0, -6.0), (7.0, -6.0, None));
+        assert_eq!(apply(&slab, 35.0, 6.0), (5.0, 6.0, None));
+
+        // With one side open there is nothing to wrap to, so it clamps
+        let floor = AxisBoundary::at_least(Axis::X, 0.0, BoundaryMode::Wrap);
+        assert_eq!(apply(&floor, -3.0, -6.0), (0.0, 0.0, None));
+        let ceiling = AxisBoundary::at_most(Axis::X, 10.0, BoundaryMode::Wrap);
+        assert_eq!(apply(&ceiling, 12.0, 6.0), (10.0, 0.0, None));
+    }
+
+    #[test]
+    fn kill_and_reset_report_an_escape() {
+        let kill = AxisBoundary::at_most(Axis::X, 10.0, BoundaryMode::Kill);
+        assert_eq!(apply(&kill, 12.0, 6.0), (12.0, 6.0, Some(Escape::Kill)));
+        assert!(kill.removes());
+
+        let reset = AxisBoundary::at_least(Axis::X, 0.0, BoundaryMode::Reset { duration: 0.25 });
+        assert_eq!(apply(&reset, -1.0, -6.0), (-1.0, -6.0, Some(Escape::Reset { duration: 0.25 })));
+        assert!(reset.removes());
+        assert!(!AxisBoundary::at_least(Axis::X, 0.0, BoundaryMode::Clamp).removes());
+    }
+
+    #[test]
+    fn apply_boundaries_stops_at_the_first_escape() {
+        let boundaries = [
+            AxisBoundary::at_least(Axis::X, 0.0, BoundaryMode::Clamp),
+            AxisBoundary::at_most(Axis::X, 10.0, BoundaryMode::Kill),
+            AxisBoundary::at_most(Axis::Y, 0.0, BoundaryMode::Clamp),
+        ];
+        let mut position = Vec3::from_coords(-5.0, 3.0, 0.0);
+        let mut velocity = Vec3::from_coords(-1.0, 1.0, 0.0);
+        assert_eq!(apply_boundaries(&boundaries, &mut position, &mut velocity), None);
+        assert_eq!(position, Vec3::from_coords(0.0, 0.0, 0.0));
+        assert_eq!(velocity, Vec3::new());
+
+        let mut position = Vec3::from_coords(15.0, 3.0, 0.0);
+        let mut velocity = Vec3::from_coords(1.0, 1.0, 0.0);
+        assert_eq!(apply_boundaries(&boundaries, &mut position, &mut velocity), Some(Escape::Kill));
+        assert_eq!(position.y, 3.0);
+    }
+}
@@ -0,0 +1,130 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Source of simulation time.
+/// All times are measured from the clock's own epoch, not from the UNIX epoch.
+pub trait Clock: Send + Sync {
+    /// Time elapsed since the clock's epoch
+    fn now(&self) -> Duration;
+
+    /// Time elapsed since the clock's epoch in whole milliseconds
+    fn now_ms(&self) -> u64 {
+        self.now().as_millis() as u64
+    }
+}
+
+/// Clock shared between the simulation subsystems
+pub type SharedClock = Arc<dyn Clock>;
+
+/// Monotonic real-time clock starting at zero when created
+#[derive(Debug, Clone)]
+pub struct MonotonicClock {
+    start: Instant,
+}
+
+impl MonotonicClock {
+    pub fn new() -> Self {
+        Self { start: Instant::now() }
+    }
+}
+
+impl Default for MonotonicClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MonotonicClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// Clock that only moves when told to, for tests and offline playback
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    nanos: AtomicU64,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move the clock forward by `d`
+    pub fn advance(&self, d: Duration) {
+        self.nanos.fetch_add(d.as_nanos() as u64, Ordering::SeqCst);
+    }
+
+    /// Jump the clock to an absolute time
+    pub fn set(&self, t: Duration) {
+        self.nanos.store(t.as_nanos() as u64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::SeqCst))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ScaleSegment {
+    base_start: Duration,
+    scaled_start: Duration,
+    scale: f64,
+}
+
+/// Clock running at a multiple of another clock's rate.
+/// A scale of zero pauses time; changing the scale never makes time jump.
+pub struct ScaledClock {
+    base: SharedClock,
+    segment: Mutex<ScaleSegment>,
+}
+
+impl ScaledClock {
+    pub fn new(base: SharedClock, scale: f64) -> Self {
+        let base_start = base.now();
+        Self {
+            base,
+            segment: Mutex::new(ScaleSegment {
+                base_start,
+                scaled_start: Duration::ZERO,
+                scale: scale.max(0.0),
+            }),
+        }
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.segment.lock().unwrap().scale
+    }
+
+    /// Change the rate of time from now on
+    pub fn set_scale(&self, scale: f64) {
+        let mut segment = self.segment.lock().unwrap();
+        let base_now = self.base.now();
+        let scaled_now = Self::scaled_time(&segment, base_now);
+        *segment = ScaleSegment {
+            base_start: base_now,
+            scaled_start: scaled_now,
+            scale: scale.max(0.0),
+        };
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.scale() == 0.0
+    }
+
+    fn scaled_time(segment: &ScaleSegment, base_now: Duration) -> Duration {
+        let elapsed = base_now.saturating_sub(segment.base_start);
+        segment.scaled_start + elapsed.mul_f64(segment.scale)
+    }
+}
+
+impl Clock for ScaledClock {
+    fn now(&self) -> Duration {
+        let segment = self.segment.lock().unwrap();
+        Self::scaled_time(&segment, self.base.now())
+    }
+}
@@ -0,0 +1,347 @@
+use crate::parallel::Execution;
+use crate::particle::Particle;
+use crate::vector::Vec3;
+use crate::wall_grid::WallGrid;
+
+/// Role of a spring in the cloth mesh
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpringKind {
+    /// Direct horizontal and vertical neighbors; resists stretching
+    Structural,
+    /// Diagonal neighbors; resists shearing
+    Shear,
+    /// Neighbors two cells away; resists folding
+    Bend,
+}
+
+/// Stiffness and damping of one kind of spring
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpringParams {
+    /// Force per unit of stretch
+    pub stiffness: f64,
+    /// Force per unit of stretching speed
+    pub damping: f64,
+    /// Length over rest length at which the spring snaps; infinite for unbreakable springs
+    pub break_strain: f64,
+}
+
+impl SpringParams {
+    pub fn new(stiffness: f64, damping: f64) -> Self {
+        Self {
+            stiffness,
+            damping,
+            break_strain: f64::INFINITY,
+        }
+    }
+
+    pub fn with_break_strain(mut self, break_strain: f64) -> Self {
+        self.break_strain = break_strain;
+        self
+    }
+}
+
+/// Parameters of the mass-spring wall model
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClothConfig {
+    pub structural: SpringParams,
+    pub shear: SpringParams,
+    pub bend: SpringParams,
+    /// Rest length as a fraction of the grid distance; below 1 the cloth is under tension,
+    /// which lets transverse ripples travel across it
+    pub rest_length_scale: f64,
+    /// Pin the outermost row and column so the tension has something to pull against
+    pub pin_edges: bool,
+    /// Collision impulse on a wall particle above which all its springs are torn off
+    pub tear_impulse: f64,
+}
+
+impl ClothConfig {
+    pub fn params(&self, kind: SpringKind) -> SpringParams {
+        match kind {
+            SpringKind::Structural => self.structural,
+            SpringKind::Shear => self.shear,
+            SpringKind::Bend => self.bend,
+        }
+    }
+}
+
+impl Default for ClothConfig {
+    fn default() -> Self {
+        Self {
+            structural: SpringParams::new(800.0, 2.0).with_break_strain(2.0),
+            shear: SpringParams::new(400.0, 1.0).with_break_strain(2.0),
+            bend: SpringParams::new(100.0, 0.5).with_break_strain(2.0),
+            rest_length_scale: 0.98,
+            pin_edges: true,
+            tear_impulse: f64::INFINITY,
+        }
+    }
+}
+
+/// Grid offsets each particle is linked to, one direction per pair
+const LINKS: [(i32, i32, SpringKind); 6] = [
+    (1, 0, SpringKind::Structural),
+    (0, 1, SpringKind::Structural),
+    (1, 1, SpringKind::Shear),
+    (1, -1, SpringKind::Shear),
+    (2, 0, SpringKind::Bend),
+    (0, 2, SpringKind::Bend),
+];
+
+/// Spring connecting two wall particles
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spring {
+    pub a: usize,
+    pub b: usize,
+    pub rest_length: f64,
+    pub kind: SpringKind,
+    /// Length over rest length at which the spring snaps
+    pub break_strain: f64,
+}
+
+impl Spring {
+    /// Spring between `a` and `b` at the rest spacing of their original positions
+    fn between(a: usize, b: usize, kind: SpringKind, particles: &[Particle], config: &ClothConfig) -> Self {
+        let rest = *particles[b].get_original_position() - *particles[a].get_original_position();
+        Self {
+            a,
+            b,
+            rest_length: rest.magnitude() * config.rest_length_scale,
+            kind,
+            break_strain: config.params(kind).break_strain,
+        }
+    }
+}
+
+/// Why a spring was removed from the mesh
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakCause {
+    /// Stretched past its break strain
+    Overstretched,
+    /// Torn off by a collision impulse on one of its particles
+    Impact,
+    /// One of its particles broke out of the wall
+    Detached,
+}
+
+/// A spring that was severed
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpringBreak {
+    pub a: usize,
+    pub b: usize,
+    pub kind: SpringKind,
+    pub cause: BreakCause,
+}
+
+/// Network of springs between `WallGrid` neighbors
+#[derive(Debug, Clone)]
+pub struct Cloth {
+    springs: Vec<Spring>,
+    original_count: usize,
+    /// Springs touching each particle, in spring order: particle `i` owns
+    /// `incident[incident_start[i]..incident_start[i + 1]]`
+    incident_start: Vec<usize>,
+    incident: Vec<usize>,
+}
+
+impl Cloth {
+    /// Connect every particle to its structural, shear and bend neighbors.
+    /// Each pair is added once, using the rest positions of `particles`.
+    pub fn from_grid(grid: &WallGrid, particles: &[Particle], config: &ClothConfig) -> Self {
+        let mut springs = Vec::new();
+        for a in 0..grid.len() {
+            for &(dc, dr, kind) in &LINKS {
+                if let Some(b) = grid.neighbor(a, dc, dr) {
+                    springs.push(Spring::between(a, b, kind, particles, config));
+                }
+            }
+        }
+
+        let original_count = springs.len();
+        let mut cloth = Self {
+            springs,
+            original_count,
+            incident_start: Vec::new(),
+            incident: Vec::new(),
+        };
+        cloth.rebuild_incidence(grid.len());
+        cloth
+    }
+
+    fn rebuild_incidence(&mut self, particle_count: usize) {
+        let mut counts = vec![0; particle_count + 1];
+        for spring in &self.springs {
+            counts[spring.a + 1] += 1;
+            counts[spring.b + 1] += 1;
+        }
+        for i in 0..particle_count {
+            counts[i + 1] += counts[i];
+        }
+
+        let mut next = counts.clone();
+        self.incident = vec![0; self.springs.len() * 2];
+        for (index, spring) in self.springs.iter().enumerate() {
+            for end in [spring.a, spring.b] {
+                self.incident[next[end]] = index;
+                next[end] += 1;
+            }
+        }
+        self.incident_start = counts;
+    }
+
+    fn particle_count(&self) -> usize {
+        self.incident_start.len().saturating_sub(1)
+    }
+
+    /// Particles connected to particle `index` by an intact spring
+    pub fn neighbors(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        self.incident[self.incident_start[index]..self.incident_start[index + 1]]
+            .iter()
+            .map(move |&spring_idx| {
+                let spring = &self.springs[spring_idx];
+                if spring.a == index { spring.b } else { spring.a }
+            })
+    }
+
+    pub fn springs(&self) -> &[Spring] {
+        &self.springs
+    }
+
+    /// Number of springs severed since the cloth was built, net of any restored
+    pub fn broken_count(&self) -> usize {
+        self.original_count.saturating_sub(self.springs.len())
+    }
+
+    /// Remove every spring stretched past its break strain
+    pub fn break_overstretched(&mut self, particles: &[Particle], events: &mut Vec<SpringBreak>) {
+        let before = self.springs.len();
+        self.springs.retain(|spring| {
+            let length = (particles[spring.b].position - particles[spring.a].position).magnitude();
+            let intact = length <= spring.rest_length * spring.break_strain;
+            if !intact {
+                events.push(SpringBreak {
+                    a: spring.a,
+                    b: spring.b,
+                    kind: spring.kind,
+                    cause: BreakCause::Overstretched,
+                });
+            }
+            intact
+        });
+        if self.springs.len() != before {
+            self.rebuild_incidence(self.particle_count());
+        }
+    }
+
+    /// Remove every spring attached to particle `index`
+    pub fn tear_at(&mut self, index: usize, events: &mut Vec<SpringBreak>) {
+        self.remove_springs_at(index, BreakCause::Impact, events);
+    }
+
+    /// Remove every spring attached to particle `index` as it leaves the wall
+    pub fn detach_at(&mut self, index: usize, events: &mut Vec<SpringBreak>) {
+        self.remove_springs_at(index, BreakCause::Detached, events);
+    }
+
+    /// Reconnect particle `index` to every neighbor `connectable` accepts, skipping springs that still exist
+    pub fn restore_at(
+        &mut self,
+        index: usize,
+        grid: &WallGrid,
+        particles: &[Particle],
+        config: &ClothConfig,
+        connectable: impl Fn(usize) -> bool,
+    ) {
+        let before = self.springs.len();
+        for &(dc, dr, kind) in &LINKS {
+            let pairs = [
+                grid.neighbor(index, dc, dr).map(|b| (index, b)),
+                grid.neighbor(index, -dc, -dr).map(|a| (a, index)),
+            ];
+            for (a, b) in pairs.into_iter().flatten() {
+                let other = if a == index { b } else { a };
+                let exists = self.springs.iter().any(|spring| spring.a == a && spring.b == b);
+                if connectable(other) && !exists {
+                    self.springs.push(Spring::between(a, b, kind, particles, config));
+                }
+            }
+        }
+        if self.springs.len() != before {
+            self.rebuild_incidence(self.particle_count());
+        }
+    }
+
+    fn remove_springs_at(&mut self, index: usize, cause: BreakCause, events: &mut Vec<SpringBreak>) {
+        let before = self.springs.len();
+        self.springs.retain(|spring| {
+            let attached = spring.a == index || spring.b == index;
+            if attached {
+                events.push(SpringBreak {
+                    a: spring.a,
+                    b: spring.b,
+                    kind: spring.kind,
+                    cause,
+                });
+            }
+            !attached
+        });
+        if self.springs.len() != before {
+            self.rebuild_incidence(self.particle_count());
+        }
+    }
+
+    /// Add each spring's force, divided by particle mass, to `out` for the trial state.
+    /// The parallel path gathers per particle over its springs in spring order, so it adds
+    /// exactly the same terms in the same order as the single-threaded scatter.
+    pub fn accumulate_accelerations(
+        &self,
+        config: &ClothConfig,
+        particles: &[Particle],
+        positions: &[Vec3],
+        velocities: &[Vec3],
+        out: &mut [Vec3],
+        execution: Execution,
+    ) {
+        match execution {
+            Execution::SingleThreaded => {
+                for spring in &self.springs {
+                    let Some(force) = spring_force(config, spring, positions, velocities) else {
+                        continue;
+                    };
+                    out[spring.a].add(&(force * particles[spring.a].inv_mass));
+                    out[spring.b].add(&(force * -particles[spring.b].inv_mass));
+                }
+            }
+            Execution::Parallel => execution.for_each_mut(out, |index, acceleration| {
+                let incident = &self.incident[self.incident_start[index]..self.incident_start[index + 1]];
+                for &spring_idx in incident {
+                    let spring = &self.springs[spring_idx];
+                    let Some(force) = spring_force(config, spring, positions, velocities) else {
+                        continue;
+                    };
+                    if spring.a == index {
+                        acceleration.add(&(force * particles[index].inv_mass));
+                    } else {
+                        acceleration.add(&(force * -particles[index].inv_mass));
+                    }
+                }
+            }),
+        }
+    }
+}
+
+/// Force a spring exerts on its `a` end; its `b` end feels the opposite.
+/// `None` when both ends coincide and there is no direction to push along.
+fn spring_force(config: &ClothConfig, spring: &Spring, positions: &[Vec3], velocities: &[Vec3]) -> Option<Vec3> {
+    let params = config.params(spring.kind);
+    let offset = positions[spring.b] - positions[spring.a];
+    let length = offset.magnitude();
+    if length <= f64::EPSILON {
+        return None;
+    }
+    let direction = offset * (1.0 / length);
+
+    let stretch = length - spring.rest_length;
+    let stretch_speed = (velocities[spring.b] - velocities[spring.a]).dot(&direction);
+    Some(direction * (params.stiffness * stretch + params.damping * stretch_speed))
+}
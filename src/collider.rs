@@ -0,0 +1,493 @@
+use crate::material::MaterialId;
+use crate::vector::Vec3;
+use std::path::Path;
+
+/// Where a sphere touches a collider
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+    /// Unit vector out of the collider, towards the sphere
+    pub normal: Vec3,
+    /// How far the sphere has sunk into the collider
+    pub depth: f64,
+}
+
+/// Solid shape particles bounce off
+pub trait Collider: Send + Sync {
+    /// Contact with a sphere at `center` with `radius`, if they overlap
+    fn contact(&self, center: &Vec3, radius: f64) -> Option<Contact>;
+
+    /// Velocity of the collider's surface at `point`; zero for static scenery
+    fn velocity_at(&self, _point: &Vec3) -> Vec3 {
+        Vec3::new()
+    }
+}
+
+/// Infinite plane; everything behind it is solid, as for a floor
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plane {
+    /// Unit normal pointing out of the solid side
+    pub normal: Vec3,
+    /// Signed distance of the plane from the origin along `normal`
+    pub offset: f64,
+}
+
+impl Plane {
+    pub fn new(normal: Vec3, offset: f64) -> Self {
+        let mut normal = normal;
+        normal.normalize();
+        Self { normal, offset }
+    }
+
+    /// Plane through `point` facing along `normal`
+    pub fn through(point: Vec3, normal: Vec3) -> Self {
+        let mut plane = Self::new(normal, 0.0);
+        plane.offset = plane.normal.dot(&point);
+        plane
+    }
+}
+
+impl Collider for Plane {
+    fn contact(&self, center: &Vec3, radius: f64) -> Option<Contact> {
+        let distance = self.normal.dot(center) - self.offset;
+        (distance < radius).then_some(Contact {
+            normal: self.normal,
+            depth: radius - distance,
+        })
+    }
+}
+
+/// Axis-aligned box
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cuboid {
+    pub center: Vec3,
+    /// Half the size along each axis
+    pub half_extents: Vec3,
+}
+
+impl Cuboid {
+    pub fn new(center: Vec3, half_extents: Vec3) -> Self {
+        Self { center, half_extents }
+    }
+
+    /// Box spanning the corners `min` and `max`
+    pub fn from_corners(min: Vec3, max: Vec3) -> Self {
+        Self::new((min + max) * 0.5, (max - min) * 0.5)
+    }
+}
+
+impl Collider for Cuboid {
+    fn contact(&self, center: &Vec3, radius: f64) -> Option<Contact> {
+        let local = *center - self.center;
+        let h = self.half_extents;
+        let closest = Vec3::from_coords(
+            local.x.clamp(-h.x, h.x),
+            local.y.clamp(-h.y, h.y),
+            local.z.clamp(-h.z, h.z),
+        );
+
+        let outside = local - closest;
+        let distance = outside.magnitude();
+        if distance > f64::EPSILON {
+            return (distance < radius).then_some(Contact {
+                normal: outside * (1.0 / distance),
+                depth: radius - distance,
+            });
+        }
+
+        // Center inside the box: push out through the nearest face
+        let faces = [
+            (h.x - local.x.abs(), Vec3::from_coords(local.x.signum(), 0.0, 0.0)),
+            (h.y - local.y.abs(), Vec3::from_coords(0.0, local.y.signum(), 0.0)),
+            (h.z - local.z.abs(), Vec3::from_coords(0.0, 0.0, local.z.signum())),
+        ];
+        let (gap, normal) = faces
+            .into_iter()
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .unwrap_or((0.0, Vec3::from_coords(0.0, 0.0, 1.0)));
+        Some(Contact {
+            normal,
+            depth: radius + gap,
+        })
+    }
+}
+
+/// Solid ball
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sphere {
+    pub center: Vec3,
+    pub radius: f64,
+}
+
+impl Sphere {
+    pub fn new(center: Vec3, radius: f64) -> Self {
+        Self { center, radius }
+    }
+}
+
+impl Collider for Sphere {
+    fn contact(&self, center: &Vec3, radius: f64) -> Option<Contact> {
+        sphere_contact(&self.center, self.radius, center, radius)
+    }
+}
+
+/// Cylinder with hemispherical caps around the segment from `a` to `b`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capsule {
+    pub a: Vec3,
+    pub b: Vec3,
+    pub radius: f64,
+}
+
+impl Capsule {
+    pub fn new(a: Vec3, b: Vec3, radius: f64) -> Self {
+        Self { a, b, radius }
+    }
+
+    /// Where along the core segment, from 0 at `a` to 1 at `b`, `point` is closest
+    pub fn segment_parameter(&self, point: &Vec3) -> f64 {
+        let ab = self.b - self.a;
+        let length_squared = ab.mag_squared();
+        if length_squared <= f64::EPSILON {
+            return 0.0;
+        }
+        ((*point - self.a).dot(&ab) / length_squared).clamp(0.0, 1.0)
+    }
+
+    /// Point on the core segment closest to `point`
+    pub fn closest_point(&self, point: &Vec3) -> Vec3 {
+        self.a + (self.b - self.a) * self.segment_parameter(point)
+    }
+}
+
+impl Collider for Capsule {
+    fn contact(&self, center: &Vec3, radius: f64) -> Option<Contact> {
+        sphere_contact(&self.closest_point(center), self.radius, center, radius)
+    }
+}
+
+/// Triangle soup, collided as a two-sided surface
+#[derive(Debug, Clone, PartialEq)]
+pub struct TriangleMesh {
+    pub vertices: Vec<Vec3>,
+    pub triangles: Vec<[usize; 3]>,
+    min: Vec3,
+    max: Vec3,
+}
+
+impl TriangleMesh {
+    /// Panics if a triangle refers to a missing vertex
+    pub fn new(vertices: Vec<Vec3>, triangles: Vec<[usize; 3]>) -> Self {
+        assert!(
+            triangles.iter().flatten().all(|&v| v < vertices.len()),
+            "triangle refers to a missing vertex"
+        );
+        let mut min = Vec3::from_coords(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+        let mut max = Vec3::from_coords(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for v in &vertices {
+            min = Vec3::from_coords(min.x.min(v.x), min.y.min(v.y), min.z.min(v.z));
+            max = Vec3::from_coords(max.x.max(v.x), max.y.max(v.y), max.z.max(v.z));
+        }
+        Self {
+            vertices,
+            triangles,
+            min,
+            max,
+        }
+    }
+
+    /// Load the vertices and faces of a Wavefront OBJ file, moved by `offset` and scaled by `scale`
+    pub fn from_obj_file(path: impl AsRef<Path>, offset: Vec3, scale: f64) -> Result<Self, Box<dyn std::error::Error>> {
+        let source = std::fs::read_to_string(path)?;
+        let mut mesh = Self::parse_obj(&source)?;
+        for v in &mut mesh.vertices {
+            *v = *v * scale + offset;
+        }
+        Ok(Self::new(mesh.vertices, mesh.triangles))
+    }
+
+    /// Parse `v` and `f` lines of OBJ text; polygons are split into triangle fans and
+    /// everything else (normals, texture coordinates, groups) is ignored
+    pub fn parse_obj(source: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut vertices = Vec::new();
+        let mut triangles = Vec::new();
+
+        for (line_number, line) in source.lines().enumerate() {
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("v") => {
+                    let coords: Vec<f64> = tokens.take(3).map(str::parse).collect::<Result<_, _>>()?;
+                    if coords.len() < 3 {
+                        return Err(format!("line {}: vertex needs three coordinates", line_number + 1).into());
+                    }
+                    vertices.push(Vec3::from_coords(coords[0], coords[1], coords[2]));
+                }
+                Some("f") => {
+                    let mut face = Vec::new();
+                    for token in tokens {
+                        // `v`, `v/vt`, `v//vn` or `v/vt/vn`; negative indices count back from the end
+                        let index: i64 = token.split('/').next().unwrap_or("").parse()?;
+                        let resolved = if index < 0 {
+                            vertices.len() as i64 + index
+                        } else {
+                            index - 1
+                        };
+                        if resolved < 0 || resolved as usize >= vertices.len() {
+                            return Err(format!("line {}: face refers to missing vertex {}", line_number + 1, index).into());
+                        }
+                        face.push(resolved as usize);
+                    }
+                    if face.len() < 3 {
+                        return Err(format!("line {}: face needs at least three vertices", line_number + 1).into());
+                    }
+                    for i in 1..face.len() - 1 {
+                        triangles.push([face[0], face[i], face[i + 1]]);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self::new(vertices, triangles))
+    }
+
+    fn bounds_overlap(&self, center: &Vec3, radius: f64) -> bool {
+        center.x + radius >= self.min.x
+            && center.x - radius <= self.max.x
+            && center.y + radius >= self.min.y
+            && center.y - radius <= self.max.y
+            && center.z + radius >= self.min.z
+            && center.z - radius <= self.max.z
+    }
+}
+
+impl Collider for TriangleMesh {
+    fn contact(&self, center: &Vec3, radius: f64) -> Option<Contact> {
+        if !self.bounds_overlap(center, radius) {
+            return None;
+        }
+
+        // Deepest contact over all triangles
+        let mut best: Option<Contact> = None;
+        for triangle in &self.triangles {
+            let [a, b, c] = triangle.map(|v| self.vertices[v]);
+            let closest = closest_on_triangle(&a, &b, &c, center);
+            let offset = *center - closest;
+            let distance = offset.magnitude();
+            if distance >= radius {
+                continue;
+            }
+
+            let normal = if distance > f64::EPSILON {
+                offset * (1.0 / distance)
+            } else {
+                let mut face_normal = (b - a).cross(&(c - a));
+                face_normal.normalize();
+                face_normal
+            };
+            let depth = radius - distance;
+            if best.is_none_or(|contact| depth > contact.depth) {
+                best = Some(Contact { normal, depth });
+            }
+        }
+        best
+    }
+}
+
+/// Index of a collider registered on `PhysicsSystem`
+pub type ColliderId = usize;
+
+/// A registered collider with the material particles bounce off it with
+pub struct ColliderSlot {
+    pub collider: Box<dyn Collider>,
+    pub material: MaterialId,
+    pub enabled: bool,
+}
+
+impl ColliderSlot {
+    pub fn new(collider: Box<dyn Collider>, material: MaterialId) -> Self {
+        Self {
+            collider,
+            material,
+            enabled: true,
+        }
+    }
+}
+
+/// Contact between a solid ball and a sphere
+fn sphere_contact(solid_center: &Vec3, solid_radius: f64, center: &Vec3, radius: f64) -> Option<Contact> {
+    let offset = *center - *solid_center;
+    let distance = offset.magnitude();
+    if distance >= solid_radius + radius {
+        return None;
+    }
+    let normal = if distance > f64::EPSILON {
+        offset * (1.0 / distance)
+    } else {
+        Vec3::from_coords(0.0, 0.0, 1.0)
+    };
+    Some(Contact {
+        normal,
+        depth: solid_radius + radius - distance,
+    })
+}
+
+/// Point on triangle `abc` closest to `p`, by Voronoi region (Ericson, Real-Time Collision Detection 5.1.5)
+fn closest_on_triangle(a: &Vec3, b: &Vec3, c: &Vec3, p: &Vec3) -> Vec3 {
+    let ab = *b - *a;
+    let ac = *c - *a;
+    let ap = *p - *a;
+    let d1 = ab.dot(&ap);
+    let d2 = ac.dot(&ap);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return *a;
+    }
+
+    let bp = *p - *b;
+    let d3 = ab.dot(&bp);
+    let d4 = ac.dot(&bp);
+    if d3 >= 0.0 && d4 <= d3 {
+        return *b;
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return *a + ab * (d1 / (d1 - d3));
+    }
+
+    let cp = *p - *c;
+    let d5 = ab.dot(&cp);
+    let d6 = ac.dot(&cp);
+    if d6 >= 0.0 && d5 <= d6 {
+        return *c;
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return *a + ac * (d2 / (d2 - d6));
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+        return *b + (*c - *b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+    }
+
+    let denom = 1.0 / (va + vb + vc);
+    *a + ab * (vb * denom) + ac * (vc * denom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: Vec3, expected: Vec3) {
+        assert!((actual - expected).magnitude() < 1e-12, "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn parse_obj_splits_polygons_into_fans() {
+        let source = "# unit square\nv 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0\nvn 0 0 1\ng square\nf 1/1/1 2/1/1 3//1 4\n";
+        let mesh = TriangleMesh::parse_obj(source).unwrap();
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.triangles, vec![[0, 1, 2], [0, 2, 3]]);
+    }
+
+    #[test]
+    fn parse_obj_counts_negative_indices_from_the_end() {
+        let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf -3 -2 -1\nv 0 0 1\nf -1 -3 -2\n";
+        let mesh = TriangleMesh::parse_obj(source).unwrap();
+        assert_eq!(mesh.triangles, vec![[0, 1, 2], [3, 1, 2]]);
+
+        assert!(TriangleMesh::parse_obj("v 0 0 0\nv 1 0 0\nv 0 1 0\nf -4 -2 -1\n").is_err());
+    }
+
+    #[test]
+    fn parse_obj_rejects_bad_lines() {
+        let vertices = "v 0 0 0\nv 1 0 0\nv 0 1 0\n";
+        // OBJ indices start at 1
+        let zero = TriangleMesh::parse_obj(&format!("{}f 0 1 2\n", vertices)).unwrap_err();
+        assert!(zero.to_string().contains("line 4"), "{}", zero);
+        assert!(TriangleMesh::parse_obj(&format!("{}f 1 2 4\n", vertices)).is_err());
+        assert!(TriangleMesh::parse_obj(&format!("{}f 1 2\n", vertices)).is_err());
+
+        let short = TriangleMesh::parse_obj("v 0 0 0\nv 1 0\n").unwrap_err();
+        assert!(short.to_string().contains("line 2"), "{}", short);
+        assert!(TriangleMesh::parse_obj("v 0 0 x\n").is_err());
+    }
+
+    #[test]
+    fn cuboid_pushes_inside_points_out_through_the_nearest_face() {
+        let cuboid = Cuboid::new(Vec3::new(), Vec3::from_coords(1.0, 2.0, 3.0));
+        let contact = cuboid.contact(&Vec3::from_coords(0.8, -0.5, 0.1), 0.5).unwrap();
+        assert_near(contact.normal, Vec3::from_coords(1.0, 0.0, 0.0));
+        assert!((contact.depth - 0.7).abs() < 1e-12);
+
+        let contact = cuboid.contact(&Vec3::from_coords(-0.2, -1.9, 0.0), 0.5).unwrap();
+        assert_near(contact.normal, Vec3::from_coords(0.0, -1.0, 0.0));
+        assert!((contact.depth - 0.6).abs() < 1e-12);
+
+        // Outside, off a corner
+        let contact = cuboid.contact(&Vec3::from_coords(1.3, 2.4, 0.0), 1.0).unwrap();
+        assert_near(contact.normal, Vec3::from_coords(0.6, 0.8, 0.0));
+        assert!((contact.depth - 0.5).abs() < 1e-12);
+        assert!(cuboid.contact(&Vec3::from_coords(1.3, 2.4, 0.0), 0.4).is_none());
+    }
+
+    #[test]
+    fn closest_point_on_triangle_covers_every_region() {
+        let (a, b, c) = (
+            Vec3::from_coords(0.0, 0.0, 0.0),
+            Vec3::from_coords(2.0, 0.0, 0.0),
+            Vec3::from_coords(0.0, 2.0, 0.0),
+        );
+        let cases = [
+            // Vertices
+            ((-1.0, -1.0, 0.5), (0.0, 0.0, 0.0)),
+            ((3.0, -1.0, 0.0), (2.0, 0.0, 0.0)),
+            ((-1.0, 3.0, 0.0), (0.0, 2.0, 0.0)),
+            // Edges
+            ((1.0, -1.0, 0.0), (1.0, 0.0, 0.0)),
+            ((-0.5, 1.0, 0.0), (0.0, 1.0, 0.0)),
+            ((2.0, 2.0, 1.0), (1.0, 1.0, 0.0)),
+            // Face
+            ((0.5, 0.5, -3.0), (0.5, 0.5, 0.0)),
+        ];
+        for ((px, py, pz), (qx, qy, qz)) in cases {
+            let closest = closest_on_triangle(&a, &b, &c, &Vec3::from_coords(px, py, pz));
+            assert_near(closest, Vec3::from_coords(qx, qy, qz));
+        }
+    }
+
+    #[test]
+    fn triangle_mesh_contacts_point_away_from_the_closest_feature() {
+        let mesh = TriangleMesh::new(
+            vec![
+                Vec3::from_coords(0.0, 0.0, 0.0),
+                Vec3::from_coords(2.0, 0.0, 0.0),
+                Vec3::from_coords(0.0, 2.0, 0.0),
+            ],
+            vec![[0, 1, 2]],
+        );
+
+        // Near an edge
+        let contact = mesh.contact(&Vec3::from_coords(1.0, -0.3, 0.4), 1.0).unwrap();
+        assert_near(contact.normal, Vec3::from_coords(0.0, -0.6, 0.8));
+        assert!((contact.depth - 0.5).abs() < 1e-12);
+
+        // Near a vertex
+        let contact = mesh.contact(&Vec3::from_coords(-0.3, -0.4, 0.0), 1.0).unwrap();
+        assert_near(contact.normal, Vec3::from_coords(-0.6, -0.8, 0.0));
+        assert!((contact.depth - 0.5).abs() < 1e-12);
+
+        // Over the face, from either side
+        let contact = mesh.contact(&Vec3::from_coords(0.5, 0.5, -0.25), 1.0).unwrap();
+        assert_near(contact.normal, Vec3::from_coords(0.0, 0.0, -1.0));
+        assert!((contact.depth - 0.75).abs() < 1e-12);
+
+        // Exactly on the surface, the face normal decides
+        let contact = mesh.contact(&Vec3::from_coords(0.5, 0.5, 0.0), 1.0).unwrap();
+        assert_near(contact.normal, Vec3::from_coords(0.0, 0.0, 1.0));
+        assert_eq!(contact.depth, 1.0);
+
+        assert!(mesh.contact(&Vec3::from_coords(1.0, -0.3, 0.4), 0.5).is_none());
+    }
+}
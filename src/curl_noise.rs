@@ -0,0 +1,240 @@
+use crate::force_field::ForceField;
+use crate::vector::Vec3;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+/// Gradients along the twelve cube edges, as in improved Perlin noise
+const GRADIENTS: [[f64; 3]; 12] = [
+    [1.0, 1.0, 0.0],
+    [-1.0, 1.0, 0.0],
+    [1.0, -1.0, 0.0],
+    [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0],
+    [-1.0, 0.0, 1.0],
+    [1.0, 0.0, -1.0],
+    [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0],
+    [0.0, -1.0, 1.0],
+    [0.0, 1.0, -1.0],
+    [0.0, -1.0, -1.0],
+];
+
+/// Each octave doubles the frequency and halves the weight of the last
+const LACUNARITY: f64 = 2.0;
+const GAIN: f64 = 0.5;
+
+/// Seeded 3D gradient noise, sampled through its analytic gradient
+#[derive(Debug, Clone)]
+struct GradientNoise {
+    permutation: [u8; 512],
+}
+
+impl GradientNoise {
+    fn new(rng: &mut ChaCha8Rng) -> Self {
+        let mut table: Vec<u8> = (0..=255).collect();
+        table.shuffle(rng);
+        let mut permutation = [0; 512];
+        for (i, entry) in permutation.iter_mut().enumerate() {
+            *entry = table[i & 255];
+        }
+        Self { permutation }
+    }
+
+    fn gradient(&self, x: usize, y: usize, z: usize) -> Vec3 {
+        let p = &self.permutation;
+        let hash = p[p[p[x & 255] as usize + (y & 255)] as usize + (z & 255)] as usize;
+        let [gx, gy, gz] = GRADIENTS[hash % GRADIENTS.len()];
+        Vec3::from_coords(gx, gy, gz)
+    }
+
+    /// Gradient of the noise at `point`
+    fn gradient_at(&self, point: &Vec3) -> Vec3 {
+        let (x0, y0, z0) = (point.x.floor(), point.y.floor(), point.z.floor());
+        let f = Vec3::from_coords(point.x - x0, point.y - y0, point.z - z0);
+        // Lattice coordinates wrap every 256 cells, so negative ones map into the table too
+        let (ix, iy, iz) = (x0.rem_euclid(256.0) as usize, y0.rem_euclid(256.0) as usize, z0.rem_euclid(256.0) as usize);
+
+        let corner = |dx: usize, dy: usize, dz: usize| {
+            let g = self.gradient(ix + dx, iy + dy, iz + dz);
+            let offset = f - Vec3::from_coords(dx as f64, dy as f64, dz as f64);
+            (g.dot(&offset), g)
+        };
+        let (va, ga) = corner(0, 0, 0);
+        let (vb, gb) = corner(1, 0, 0);
+        let (vc, gc) = corner(0, 1, 0);
+        let (vd, gd) = corner(1, 1, 0);
+        let (ve, ge) = corner(0, 0, 1);
+        let (vf, gf) = corner(1, 0, 1);
+        let (vg, gg) = corner(0, 1, 1);
+        let (vh, gh) = corner(1, 1, 1);
+
+        let (u, du) = fade(f.x);
+        let (v, dv) = fade(f.y);
+        let (w, dw) = fade(f.z);
+
+        // Derivative of the trilinear blend va + u·k1 + v·k2 + w·k3 + uv·k4 + vw·k5 + wu·k6 + uvw·k7
+        let k1 = vb - va;
+        let k2 = vc - va;
+        let k3 = ve - va;
+        let k4 = va - vb - vc + vd;
+        let k5 = va - vc - ve + vg;
+        let k6 = va - vb - ve + vf;
+        let k7 = -va + vb + vc - vd + ve - vf - vg + vh;
+
+        let blended = ga
+            + (gb - ga) * u
+            + (gc - ga) * v
+            + (ge - ga) * w
+            + (ga - gb - gc + gd) * (u * v)
+            + (ga - gc - ge + gg) * (v * w)
+            + (ga - gb - ge + gf) * (w * u)
+            + (gb + gc + ge + gh - ga - gd - gf - gg) * (u * v * w);
+        let fade_terms = Vec3::from_coords(
+            du * (k1 + v * k4 + w * k6 + v * w * k7),
+            dv * (k2 + w * k5 + u * k4 + w * u * k7),
+            dw * (k3 + u * k6 + v * k5 + u * v * k7),
+        );
+        blended + fade_terms
+    }
+}
+
+/// Quintic fade curve and its derivative
+fn fade(t: f64) -> (f64, f64) {
+    let value = t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+    let derivative = 30.0 * t * t * (t * (t - 2.0) + 1.0);
+    (value, derivative)
+}
+
+/// Divergence-free turbulent flow that slowly changes over time.
+/// Particles are dragged towards the local flow velocity, like `Wind` with a swirling wind.
+#[derive(Debug, Clone)]
+pub struct CurlNoise {
+    /// Spatial frequency of the coarsest octave, in 1/units
+    pub frequency: f64,
+    /// Typical flow speed, in units/s
+    pub amplitude: f64,
+    /// Noise layers, each twice as fine and half as strong as the last
+    pub octaves: u32,
+    /// How quickly particles take up the flow, in 1/s
+    pub drag: f64,
+    /// How quickly the pattern changes, in noise cells per second
+    pub evolution: f64,
+    time: f64,
+    potentials: [GradientNoise; 3],
+}
+
+impl CurlNoise {
+    /// The same `seed` always produces the same flow
+    pub fn new(seed: u64, frequency: f64, amplitude: f64, octaves: u32) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        Self {
+            frequency,
+            amplitude,
+            octaves,
+            drag: 2.0,
+            evolution: 0.2,
+            time: 0.0,
+            potentials: [
+                GradientNoise::new(&mut rng),
+                GradientNoise::new(&mut rng),
+                GradientNoise::new(&mut rng),
+            ],
+        }
+    }
+
+    /// Flow velocity at `position`: the curl of a noise vector potential
+    pub fn velocity(&self, position: &Vec3) -> Vec3 {
+        let mut curl = Vec3::new();
+        let mut frequency = self.frequency;
+        let mut weight = 1.0;
+        let mut total_weight = 0.0;
+        for _ in 0..self.octaves {
+            let point = *position * frequency;
+            // Each potential drifts through its own noise along a different axis as time passes,
+            // so the pattern churns instead of sliding
+            let drift = self.time * self.evolution;
+            let dx = self.potentials[0].gradient_at(&(point + Vec3::from_coords(0.0, drift, 0.0)));
+            let dy = self.potentials[1].gradient_at(&(point + Vec3::from_coords(0.0, 0.0, drift)));
+            let dz = self.potentials[2].gradient_at(&(point + Vec3::from_coords(drift, 0.0, 0.0)));
+            let octave = Vec3::from_coords(dz.y - dy.z, dx.z - dz.x, dy.x - dx.y);
+            curl.add(&(octave * weight));
+
+            total_weight += weight;
+            frequency *= LACUNARITY;
+            weight *= GAIN;
+        }
+        if total_weight == 0.0 {
+            return curl;
+        }
+        curl * (self.amplitude / total_weight)
+    }
+}
+
+impl ForceField for CurlNoise {
+    fn acceleration(&self, position: &Vec3, velocity: &Vec3) -> Vec3 {
+        (self.velocity(position) - *velocity) * self.drag
+    }
+
+    fn advance(&mut self, dt: f64) {
+        self.time += dt;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Scattered sample points spanning many noise cells, including negative coordinates
+    fn sample_points() -> impl Iterator<Item = Vec3> {
+        (0..200).map(|i| {
+            let i = i as f64;
+            Vec3::from_coords((i * 37.1).sin() * 300.0, (i * 11.7).cos() * 300.0, (i * 5.3).sin() * 300.0)
+        })
+    }
+
+    #[test]
+    fn seed_determines_the_flow() {
+        let mut a = CurlNoise::new(42, 0.01, 50.0, 3);
+        let mut b = CurlNoise::new(42, 0.01, 50.0, 3);
+        let c = CurlNoise::new(43, 0.01, 50.0, 3);
+        for point in sample_points() {
+            assert_eq!(a.velocity(&point), b.velocity(&point));
+        }
+        assert!(sample_points().any(|point| a.velocity(&point) != c.velocity(&point)));
+
+        // Evolving over the same time keeps the two in step, and changes the pattern
+        let before: Vec<Vec3> = sample_points().map(|point| a.velocity(&point)).collect();
+        a.advance(1.5);
+        b.advance(1.5);
+        for point in sample_points() {
+            assert_eq!(a.velocity(&point), b.velocity(&point));
+        }
+        assert!(sample_points().zip(&before).any(|(point, old)| a.velocity(&point) != *old));
+    }
+
+    #[test]
+    fn flow_is_divergence_free() {
+        let (frequency, amplitude) = (0.01, 50.0);
+        let mut noise = CurlNoise::new(7, frequency, amplitude, 3);
+        noise.advance(2.0);
+
+        let h = 1e-4;
+        let step = |x: f64, y: f64, z: f64| Vec3::from_coords(x, y, z);
+        for point in sample_points() {
+            let velocity = |offset: Vec3| noise.velocity(&(point + offset));
+            let divergence = (velocity(step(h, 0.0, 0.0)).x - velocity(step(-h, 0.0, 0.0)).x
+                + velocity(step(0.0, h, 0.0)).y
+                - velocity(step(0.0, -h, 0.0)).y
+                + velocity(step(0.0, 0.0, h)).z
+                - velocity(step(0.0, 0.0, -h)).z)
+                / (2.0 * h);
+            // Relative to the size of the velocity gradients, amplitude × frequency
+            assert!(divergence.abs() < 1e-6 * amplitude * frequency, "divergence {} at {:?}", divergence, point);
+        }
+
+        // The flow is not trivially zero
+        let speed: f64 = sample_points().map(|point| noise.velocity(&point).magnitude()).sum::<f64>() / 200.0;
+        assert!(speed > amplitude * 0.1, "mean speed {}", speed);
+    }
+}
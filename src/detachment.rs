@@ -0,0 +1,55 @@
+use crate::boundary::ResetAnimation;
+
+/// When wall particles break out of the wall and when they come back
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetachConfig {
+    /// Distance from the rest position beyond which a particle breaks free
+    pub displacement: f64,
+    /// Kinetic energy a single collision must transfer to knock a particle free
+    pub impact_energy: f64,
+    /// Seconds a detached particle stays loose before flying back into its slot
+    pub respawn_after: f64,
+    /// Seconds the flight back into the slot takes
+    pub respawn_duration: f64,
+}
+
+impl Default for DetachConfig {
+    /// Never detach; set a finite threshold to enable
+    fn default() -> Self {
+        Self {
+            displacement: f64::INFINITY,
+            impact_energy: f64::INFINITY,
+            respawn_after: 5.0,
+            respawn_duration: 1.0,
+        }
+    }
+}
+
+/// What a wall particle is currently doing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WallState {
+    /// Held in the wall by the wall model
+    Attached,
+    /// Broken free and falling under gravity; respawns when `remaining` seconds run out
+    Detached { remaining: f64 },
+    /// Gliding back to its rest position; `reattach` restores its springs on arrival
+    Returning { animation: ResetAnimation, reattach: bool },
+}
+
+impl WallState {
+    pub fn is_attached(&self) -> bool {
+        matches!(self, WallState::Attached)
+    }
+
+    pub fn is_detached(&self) -> bool {
+        matches!(self, WallState::Detached { .. })
+    }
+
+    /// Out of the wall: detached, or flying back after having been
+    pub fn is_loose(&self) -> bool {
+        matches!(
+            self,
+            WallState::Detached { .. } | WallState::Returning { reattach: true, .. }
+        )
+    }
+}
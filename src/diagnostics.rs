@@ -0,0 +1,145 @@
+use crate::vector::Vec3;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Events counted over one frame, i.e. one `PhysicsSystem::update`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameCounts {
+    /// Fixed steps run
+    pub steps: u32,
+    /// Force particles hitting wall particles, plus loose wall particles hitting each other
+    pub collisions: u64,
+    /// Force particles spawned by emitters
+    pub spawned: u64,
+    /// Force particles removed at the end of their lifetime
+    pub expired: u64,
+    /// Spawns refused because the particle budget was full
+    pub dropped_spawns: u64,
+    /// Old force particles removed early to make room for new ones
+    pub recycled: u64,
+}
+
+/// Snapshot of the simulation's energy, momentum and activity
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhysicsStats {
+    /// Simulated time in seconds
+    pub time: f64,
+    pub wall_particles: usize,
+    pub force_particles: usize,
+    /// Kinetic energy of every particle, wall and force
+    pub kinetic_energy: f64,
+    /// Energy stored in the rest springs of attached wall particles and, with the cloth
+    /// model, in the cloth springs. The module pull is not a conservative spring and is left out.
+    pub spring_potential_energy: f64,
+    /// Total linear momentum of every particle
+    pub momentum: Vec3,
+    /// Largest distance of an attached wall particle from its rest position
+    pub max_displacement: f64,
+    /// Largest particle speed, wall or force
+    pub max_velocity: f64,
+    /// What happened during the last frame
+    pub counts: FrameCounts,
+}
+
+impl PhysicsStats {
+    pub fn total_energy(&self) -> f64 {
+        self.kinetic_energy + self.spring_potential_energy
+    }
+}
+
+/// Writes one CSV row of `PhysicsStats` per frame
+pub struct StatsCsv {
+    writer: BufWriter<File>,
+}
+
+impl StatsCsv {
+    const HEADER: &'static str = "time,steps,wall_particles,force_particles,kinetic_energy,spring_potential_energy,\
+        total_energy,momentum_x,momentum_y,momentum_z,max_displacement,max_velocity,collisions,spawned,expired,\
+        dropped_spawns,recycled";
+
+    /// Create or truncate the file at `path` and write the header
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", Self::HEADER)?;
+        Ok(Self { writer })
+    }
+
+    pub fn write(&mut self, stats: &PhysicsStats) -> io::Result<()> {
+        let counts = &stats.counts;
+        writeln!(
+            self.writer,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            stats.time,
+            counts.steps,
+            stats.wall_particles,
+            stats.force_particles,
+            stats.kinetic_energy,
+            stats.spring_potential_energy,
+            stats.total_energy(),
+            stats.momentum.x,
+            stats.momentum.y,
+            stats.momentum.z,
+            stats.max_displacement,
+            stats.max_velocity,
+            counts.collisions,
+            counts.spawned,
+            counts.expired,
+            counts.dropped_spawns,
+            counts.recycled,
+        )
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_rows_match_the_header() {
+        let path = std::env::temp_dir().join(format!("physics_stats_{}.csv", std::process::id()));
+        let stats = PhysicsStats {
+            time: 1.5,
+            wall_particles: 800,
+            force_particles: 42,
+            kinetic_energy: 12.25,
+            spring_potential_energy: 0.5,
+            momentum: Vec3::from_coords(1.0, -2.0, 3.0),
+            max_displacement: 0.125,
+            max_velocity: 300.0,
+            counts: FrameCounts {
+                steps: 2,
+                collisions: 7,
+                spawned: 500,
+                expired: 3,
+                dropped_spawns: 1,
+                recycled: 4,
+            },
+        };
+
+        let mut csv = StatsCsv::create(&path).unwrap();
+        csv.write(&stats).unwrap();
+        csv.write(&PhysicsStats { time: 1.75, ..stats }).unwrap();
+        csv.flush().unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 3);
+        let header: Vec<&str> = lines[0].split(',').collect();
+        assert!(header.iter().all(|name| !name.is_empty() && name.trim() == *name), "{:?}", header);
+        for row in &lines[1..] {
+            let fields: Vec<&str> = row.split(',').collect();
+            assert_eq!(fields.len(), header.len(), "{}", row);
+            let column = |name: &str| fields[header.iter().position(|h| *h == name).unwrap()];
+            assert_eq!(column("total_energy"), "12.75");
+            assert_eq!(column("momentum_y"), "-2");
+            assert_eq!(column("recycled"), "4");
+        }
+        assert!(lines[1].starts_with("1.5,2,800,42,"));
+    }
+}
@@ -0,0 +1,143 @@
+use crate::spatial_hash::SpatialHash;
+use crate::vector::Vec3;
+
+/// Coulomb interaction between charged particles. Every pair of charges within the cutoff is
+/// evaluated, so dense clouds of charged particles get expensive quickly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElectrostaticsConfig {
+    pub enabled: bool,
+    /// Coulomb constant: the force between two unit charges one unit apart
+    pub coulomb: f64,
+    /// Added in quadrature to the distance, so close encounters stay finite
+    pub softening: f64,
+    /// Charges further apart than this do not interact
+    pub cutoff: f64,
+}
+
+impl Default for ElectrostaticsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            coulomb: 200.0,
+            softening: 5.0,
+            cutoff: 20.0,
+        }
+    }
+}
+
+/// A charged particle taking part in one evaluation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointCharge {
+    pub position: Vec3,
+    pub charge: f64,
+    pub inv_mass: f64,
+    /// Wall particles are held in place by the wall model, so they do not act on each other
+    pub wall: bool,
+    /// Index in the wall or force particle set
+    pub index: usize,
+}
+
+/// Acceleration of `charges[index]` from every other charge within the cutoff.
+/// `hash` must index `charges` by position with cells at least `config.cutoff` wide.
+pub fn acceleration(
+    config: &ElectrostaticsConfig,
+    charges: &[PointCharge],
+    hash: &SpatialHash,
+    index: usize,
+    candidates: &mut Vec<usize>,
+) -> Vec3 {
+    let this = &charges[index];
+    if this.inv_mass == 0.0 {
+        return Vec3::new();
+    }
+
+    candidates.clear();
+    hash.query(&this.position, config.cutoff, candidates);
+
+    let cutoff_squared = config.cutoff * config.cutoff;
+    let softening_squared = config.softening * config.softening;
+    let mut field = Vec3::new();
+    for &other_idx in candidates.iter() {
+        let other = &charges[other_idx];
+        if other_idx == index || (this.wall && other.wall) {
+            continue;
+        }
+        let offset = this.position - other.position;
+        let distance_squared = offset.mag_squared();
+        if distance_squared > cutoff_squared {
+            continue;
+        }
+        let softened = distance_squared + softening_squared;
+        field.add(&(offset * (other.charge / (softened * softened.sqrt()))));
+    }
+    field * (config.coulomb * this.charge * this.inv_mass)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn charge(x: f64, charge: f64, wall: bool) -> PointCharge {
+        PointCharge {
+            position: Vec3::from_coords(x, 0.0, 0.0),
+            charge,
+            inv_mass: 1.0,
+            wall,
+            index: 0,
+        }
+    }
+
+    /// Acceleration of every charge in `charges` under the default config
+    fn accelerations(charges: &[PointCharge]) -> Vec<Vec3> {
+        let config = ElectrostaticsConfig::default();
+        let mut hash = SpatialHash::new();
+        hash.rebuild_points(charges.iter().map(|charge| charge.position), config.cutoff);
+        let mut candidates = Vec::new();
+        (0..charges.len())
+            .map(|index| acceleration(&config, charges, &hash, index, &mut candidates))
+            .collect()
+    }
+
+    #[test]
+    fn opposite_charges_attract_and_like_charges_repel() {
+        let unlike = accelerations(&[charge(-5.0, 1.0, false), charge(5.0, -1.0, false)]);
+        assert!(unlike[0].x > 0.0 && unlike[1].x < 0.0);
+
+        let like = accelerations(&[charge(-5.0, -1.0, false), charge(5.0, -1.0, false)]);
+        assert!(like[0].x < 0.0 && like[1].x > 0.0);
+        // Equal masses, so the pair's momentum is conserved
+        assert_eq!(like[0].x, -like[1].x);
+    }
+
+    #[test]
+    fn charges_beyond_the_cutoff_do_not_interact() {
+        let cutoff = ElectrostaticsConfig::default().cutoff;
+        let inside = accelerations(&[charge(0.0, 1.0, false), charge(cutoff - 0.1, 1.0, false)]);
+        assert!(inside[0].magnitude() > 0.0);
+        let outside = accelerations(&[charge(0.0, 1.0, false), charge(cutoff + 0.1, 1.0, false)]);
+        assert_eq!(outside[0].magnitude(), 0.0);
+        assert_eq!(outside[1].magnitude(), 0.0);
+    }
+
+    #[test]
+    fn softening_keeps_close_encounters_finite() {
+        let config = ElectrostaticsConfig::default();
+        // r / (r² + s²)^(3/2) never exceeds 1 / s²
+        let bound = config.coulomb / (config.softening * config.softening);
+        for distance in [0.0, 1e-12, 1e-6, 0.1, 1.0, config.softening] {
+            let pair = accelerations(&[charge(0.0, 1.0, false), charge(distance, -1.0, false)]);
+            assert!(pair[0].magnitude().is_finite());
+            assert!(pair[0].magnitude() <= bound, "{} at distance {}", pair[0].magnitude(), distance);
+        }
+    }
+
+    #[test]
+    fn wall_charges_only_act_on_force_particles() {
+        let walls = accelerations(&[charge(-5.0, 1.0, true), charge(5.0, 1.0, true)]);
+        assert_eq!(walls[0].magnitude(), 0.0);
+        assert_eq!(walls[1].magnitude(), 0.0);
+
+        let mixed = accelerations(&[charge(-5.0, 1.0, true), charge(5.0, 1.0, false)]);
+        assert!(mixed[0].x < 0.0 && mixed[1].x > 0.0);
+    }
+}
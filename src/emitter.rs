@@ -0,0 +1,401 @@
+use crate::attributes::{Rgba, WHITE};
+use crate::force_field::JointAttachment;
+use crate::input::BodyJoints;
+use crate::material::{MaterialId, FORCE_MATERIAL};
+use crate::particle::Particle;
+use crate::vector::Vec3;
+use rand::Rng;
+
+/// Region new particles are spawned in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmitterShape {
+    /// All particles start at the emitter position
+    Point,
+    /// Uniformly inside a ball around the emitter position
+    Sphere { radius: f64 },
+    /// From the emitter position, heading within `half_angle` radians of the source velocity
+    Cone { half_angle: f64 },
+    /// Inside a disc facing along the source velocity
+    Disc { radius: f64 },
+}
+
+/// How many particles an emitter produces
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmissionRate {
+    /// A steady stream of particles per second
+    Continuous { per_second: f64 },
+    /// `count` particles at once, every `interval` seconds from when the emitter turns on
+    Burst { count: usize, interval: f64 },
+}
+
+/// Inclusive range a per-particle value is drawn from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ValueRange {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl ValueRange {
+    pub fn new(min: f64, max: f64) -> Self {
+        Self { min, max }
+    }
+
+    pub fn constant(value: f64) -> Self {
+        Self::new(value, value)
+    }
+
+    pub fn sample(&self, rng: &mut impl Rng) -> f64 {
+        if self.max > self.min {
+            rng.gen_range(self.min..=self.max)
+        } else {
+            self.min
+        }
+    }
+}
+
+/// Parameters of an emitter
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmitterConfig {
+    pub shape: EmitterShape,
+    pub rate: EmissionRate,
+    /// Particle velocity per unit of source velocity
+    pub speed_scale: f64,
+    /// Random velocity added to each particle, up to this speed in units/s
+    pub velocity_jitter: f64,
+    /// Nothing is emitted while the source moves slower than this, in units/s
+    pub min_source_speed: f64,
+    pub radius: ValueRange,
+    pub mass: ValueRange,
+    /// Extra mass per unit of source speed
+    pub mass_per_speed: f64,
+    /// Particle lifetime in seconds
+    pub lifetime: ValueRange,
+    pub material: MaterialId,
+    pub color: Rgba,
+    pub temperature: ValueRange,
+    pub charge: ValueRange,
+    /// Copied into every particle's `user_data` attribute
+    pub user_data: u64,
+    /// Joint the emitter follows; its velocity becomes the source velocity
+    pub attachment: Option<JointAttachment>,
+}
+
+impl Default for EmitterConfig {
+    /// The original hand emitter: 500 particles per frame at 60 Hz, living five seconds
+    fn default() -> Self {
+        Self {
+            shape: EmitterShape::Sphere { radius: 0.0 },
+            rate: EmissionRate::Continuous { per_second: 30000.0 },
+            speed_scale: 2.4,
+            velocity_jitter: 0.0,
+            min_source_speed: 1.0,
+            radius: ValueRange::constant(1.0),
+            mass: ValueRange::constant(1.0),
+            mass_per_speed: 0.0,
+            lifetime: ValueRange::constant(5.0),
+            material: FORCE_MATERIAL,
+            color: WHITE,
+            temperature: ValueRange::constant(0.0),
+            charge: ValueRange::constant(0.0),
+            user_data: 0,
+            attachment: None,
+        }
+    }
+}
+
+/// Index of an emitter registered on `PhysicsSystem`
+pub type EmitterId = usize;
+
+/// Spawns force particles from a moving source
+#[derive(Debug, Clone)]
+pub struct Emitter {
+    pub config: EmitterConfig,
+    pub position: Vec3,
+    /// Velocity of the source, e.g. the hand the emitter is attached to
+    pub source_velocity: Vec3,
+    pub enabled: bool,
+    pending: f64,
+    burst_timer: f64,
+}
+
+impl Emitter {
+    pub fn new(config: EmitterConfig) -> Self {
+        Self {
+            // Attached emitters wait until they have been placed on the body
+            enabled: config.attachment.is_none(),
+            config,
+            position: Vec3::new(),
+            source_velocity: Vec3::new(),
+            pending: 0.0,
+            burst_timer: 0.0,
+        }
+    }
+
+    /// Move the emitter and set the velocity particles inherit
+    pub fn set_source(&mut self, position: Vec3, velocity: Vec3) {
+        self.position = position;
+        self.source_velocity = velocity;
+    }
+
+    /// Follow the attached joint, if any, and switch on or off for the current pose
+    pub fn update_attachment(&mut self, body: &BodyJoints) {
+        if let Some(attachment) = self.config.attachment {
+            self.enabled = attachment.is_active(body);
+            let velocity = body.hand(attachment.joint).map(|hand| hand.velocity).unwrap_or_default();
+            self.set_source(body.position(attachment.joint) + attachment.offset, velocity);
+        }
+    }
+
+    /// Number of particles due over `dt` seconds, carrying fractions over.
+    /// Callers that spawn fewer than this (e.g. under a particle budget) simply drop the rest.
+    pub fn due(&mut self, dt: f64) -> usize {
+        let active = self.enabled && self.source_velocity.magnitude() >= self.config.min_source_speed;
+        if !active {
+            self.pending = 0.0;
+            self.burst_timer = 0.0;
+            return 0;
+        }
+
+        match self.config.rate {
+            EmissionRate::Continuous { per_second } => {
+                // Rounded rather than floored, so the running total stays within half a
+                // particle of the rate times the time so far
+                self.pending += per_second * dt;
+                let count = (self.pending + ROUNDING_SLACK).round();
+                self.pending -= count;
+                count as usize
+            }
+            EmissionRate::Burst { count, interval } => {
+                // A burst is due at the start of the step it falls in
+                let fire = self.burst_timer <= ROUNDING_SLACK;
+                if fire {
+                    self.burst_timer += interval.max(dt);
+                }
+                self.burst_timer -= dt;
+                if fire {
+                    count
+                } else {
+                    0
+                }
+            }
+        }
+    }
+
+    /// Create one particle from the emitter's current state
+    pub fn spawn(&self, now_ms: u64, rng: &mut impl Rng) -> Particle {
+        let speed = self.source_velocity.magnitude();
+        let mut direction = self.source_velocity;
+        direction.normalize();
+        if direction.mag_squared() == 0.0 {
+            direction = Vec3::from_coords(0.0, 0.0, 1.0);
+        }
+
+        let mut offset = Vec3::new();
+        let mut velocity = self.source_velocity * self.config.speed_scale;
+        match self.config.shape {
+            EmitterShape::Point => {}
+            EmitterShape::Sphere { radius } => {
+                offset = random_in_unit_sphere(rng) * radius;
+            }
+            EmitterShape::Cone { half_angle } => {
+                velocity = random_in_cone(rng, &direction, half_angle) * (speed * self.config.speed_scale);
+            }
+            EmitterShape::Disc { radius } => {
+                let (u, v) = perpendicular_basis(&direction);
+                let r = radius * rng.gen::<f64>().sqrt();
+                let theta = rng.gen::<f64>() * std::f64::consts::TAU;
+                offset = u * (r * theta.cos()) + v * (r * theta.sin());
+            }
+        }
+        velocity.add(&(random_in_unit_sphere(rng) * self.config.velocity_jitter));
+
+        let position = self.position + offset;
+        let mut particle = Particle::new(position.x, position.y, position.z);
+        particle.set_radius(self.config.radius.sample(rng));
+        particle.set_mass(self.config.mass.sample(rng) * (1.0 + speed * self.config.mass_per_speed));
+        particle.material = self.config.material;
+        particle.created_time = now_ms;
+        particle.lifetime_ms = (self.config.lifetime.sample(rng) * 1000.0) as u64;
+        particle.velocity = velocity;
+        particle.attributes.color = self.config.color;
+        particle.attributes.temperature = self.config.temperature.sample(rng);
+        particle.attributes.charge = self.config.charge.sample(rng);
+        particle.attributes.user_data = self.config.user_data;
+        particle
+    }
+}
+
+/// Rounding error tolerated when steps add up to a whole particle or burst interval,
+/// so the output does not depend on how time is divided into steps
+const ROUNDING_SLACK: f64 = 1e-9;
+
+/// Uniform random point inside the unit ball
+fn random_in_unit_sphere(rng: &mut impl Rng) -> Vec3 {
+    loop {
+        let p = Vec3::from_coords(
+            rng.gen::<f64>() * 2.0 - 1.0,
+            rng.gen::<f64>() * 2.0 - 1.0,
+            rng.gen::<f64>() * 2.0 - 1.0,
+        );
+        if p.mag_squared() <= 1.0 {
+            return p;
+        }
+    }
+}
+
+/// Uniform random unit vector within `half_angle` radians of `axis`
+fn random_in_cone(rng: &mut impl Rng, axis: &Vec3, half_angle: f64) -> Vec3 {
+    let (u, v) = perpendicular_basis(axis);
+    let cos_theta = 1.0 - rng.gen::<f64>() * (1.0 - half_angle.cos());
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = rng.gen::<f64>() * std::f64::consts::TAU;
+    *axis * cos_theta + u * (sin_theta * phi.cos()) + v * (sin_theta * phi.sin())
+}
+
+/// Two unit vectors perpendicular to the unit vector `axis` and to each other
+fn perpendicular_basis(axis: &Vec3) -> (Vec3, Vec3) {
+    let helper = if axis.x.abs() < 0.9 {
+        Vec3::from_coords(1.0, 0.0, 0.0)
+    } else {
+        Vec3::from_coords(0.0, 1.0, 0.0)
+    };
+    let mut u = axis.cross(&helper);
+    u.normalize();
+    let v = axis.cross(&u);
+    (u, v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn moving_emitter(shape: EmitterShape, rate: EmissionRate) -> Emitter {
+        let mut emitter = Emitter::new(EmitterConfig {
+            shape,
+            rate,
+            ..EmitterConfig::default()
+        });
+        emitter.set_source(Vec3::from_coords(10.0, -20.0, 30.0), Vec3::from_coords(30.0, 0.0, 40.0));
+        emitter
+    }
+
+    #[test]
+    fn continuous_rate_does_not_depend_on_the_step_size() {
+        let seconds = 3.0;
+        for per_second in [1.0, 60.0, 250.0, 1234.5, 30000.0] {
+            for steps_per_second in [1, 7, 30, 60, 144, 1000] {
+                let mut emitter = moving_emitter(EmitterShape::Point, EmissionRate::Continuous { per_second });
+                let dt = 1.0 / steps_per_second as f64;
+                let total: usize = (0..steps_per_second * seconds as usize).map(|_| emitter.due(dt)).sum();
+                assert_eq!(
+                    total,
+                    (seconds * per_second).round() as usize,
+                    "{} per second in steps of {}",
+                    per_second,
+                    dt
+                );
+            }
+
+            // Steps of uneven length adding up to the same time
+            let mut emitter = moving_emitter(EmitterShape::Point, EmissionRate::Continuous { per_second });
+            let pattern = [0.004, 0.016, 0.0333, 0.0467, 0.1];
+            let total: usize = (0..15).flat_map(|_| pattern).map(|dt| emitter.due(dt)).sum();
+            assert_eq!(total, (seconds * per_second).round() as usize, "{} per second, uneven steps", per_second);
+        }
+    }
+
+    #[test]
+    fn bursts_fire_once_per_interval() {
+        let mut emitter = moving_emitter(EmitterShape::Point, EmissionRate::Burst { count: 5, interval: 0.5 });
+        let fired: Vec<usize> = (0..16).filter(|_| emitter.due(0.125) > 0).collect();
+        assert_eq!(fired, vec![0, 4, 8, 12]);
+
+        // An interval shorter than a step fires every step, never several bursts at once
+        let mut emitter = moving_emitter(EmitterShape::Point, EmissionRate::Burst { count: 5, interval: 0.01 });
+        assert!((0..10).all(|_| emitter.due(0.125) == 5));
+    }
+
+    #[test]
+    fn slow_or_disabled_sources_emit_nothing() {
+        // The source moves at 50 units/s
+        let mut emitter = moving_emitter(EmitterShape::Point, EmissionRate::Continuous { per_second: 90.0 });
+        emitter.config.min_source_speed = 60.0;
+        assert_eq!(emitter.due(1.0), 0);
+        emitter.config.min_source_speed = 45.0;
+        assert_eq!(emitter.due(1.0), 90);
+
+        // Below the gate the carried fraction is dropped, so resuming starts from scratch
+        assert_eq!(emitter.due(0.004), 0);
+        emitter.source_velocity = Vec3::from_coords(0.0, 0.0, 1.0);
+        assert_eq!(emitter.due(1.0), 0);
+        emitter.source_velocity = Vec3::from_coords(0.0, 0.0, 50.0);
+        assert_eq!(emitter.due(0.004), 0);
+        assert_eq!(emitter.due(0.004), 1);
+
+        emitter.enabled = false;
+        assert_eq!(emitter.due(1.0), 0);
+    }
+
+    #[test]
+    fn spawns_stay_inside_the_shape() {
+        let mut rng = ChaCha8Rng::seed_from_u64(11);
+        let shapes = [
+            EmitterShape::Point,
+            EmitterShape::Sphere { radius: 4.0 },
+            EmitterShape::Cone { half_angle: 0.3 },
+            EmitterShape::Disc { radius: 6.0 },
+        ];
+        for shape in shapes {
+            let emitter = moving_emitter(shape, EmissionRate::Continuous { per_second: 1.0 });
+            let direction = Vec3::from_coords(0.6, 0.0, 0.8);
+            let speed = 50.0 * emitter.config.speed_scale;
+            let mut farthest: f64 = 0.0;
+            for _ in 0..500 {
+                let particle = emitter.spawn(0, &mut rng);
+                let offset = particle.position - emitter.position;
+                farthest = farthest.max(offset.magnitude());
+                match shape {
+                    EmitterShape::Point | EmitterShape::Cone { .. } => assert_eq!(offset.magnitude(), 0.0),
+                    EmitterShape::Sphere { radius } => assert!(offset.magnitude() <= radius),
+                    EmitterShape::Disc { radius } => {
+                        assert!(offset.magnitude() <= radius);
+                        assert!(offset.dot(&direction).abs() < 1e-9, "disc offset leaves its plane");
+                    }
+                }
+                match shape {
+                    EmitterShape::Cone { half_angle } => {
+                        let velocity = particle.velocity;
+                        assert!((velocity.magnitude() - speed).abs() < 1e-9);
+                        assert!(velocity.dot(&direction) / velocity.magnitude() >= half_angle.cos() - 1e-12);
+                    }
+                    _ => assert_eq!(particle.velocity, emitter.source_velocity * emitter.config.speed_scale),
+                }
+            }
+            // The samples fill the shape rather than bunching at its center
+            match shape {
+                EmitterShape::Sphere { radius } | EmitterShape::Disc { radius } => assert!(farthest > 0.9 * radius),
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_spawns_the_same_particles() {
+        let mut emitter = moving_emitter(EmitterShape::Sphere { radius: 4.0 }, EmissionRate::Continuous { per_second: 1.0 });
+        emitter.config.velocity_jitter = 5.0;
+        emitter.config.lifetime = ValueRange::new(1.0, 2.0);
+        let spawn_all = |seed| {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            (0..20)
+                .map(|_| {
+                    let particle = emitter.spawn(0, &mut rng);
+                    (particle.position, particle.velocity, particle.lifetime_ms)
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(spawn_all(3), spawn_all(3));
+        assert_ne!(spawn_all(3), spawn_all(4));
+    }
+}
@@ -0,0 +1,45 @@
+use crate::emitter::EmitterId;
+use crate::particle_store::ParticleHandle;
+use crate::vector::Vec3;
+
+/// Why a force particle was removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RemovalCause {
+    /// Reached the end of its lifetime
+    Expired,
+    /// Hit the wall while `consume_force_particles` is on
+    Consumed,
+    /// Removed early to make room under the particle budget
+    Recycled,
+    /// Crossed a force boundary that removes particles
+    OutOfBounds,
+}
+
+/// Something that happened to a particle during a step
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PhysicsEvent {
+    /// An emitter created a force particle
+    Spawned {
+        handle: ParticleHandle,
+        emitter: EmitterId,
+        position: Vec3,
+    },
+    /// A force particle hit wall particle `wall_index`, exchanging a normal impulse of
+    /// `impulse` at `point`
+    Collided { wall_index: usize, impulse: f64, point: Vec3 },
+    /// A force particle left the simulation. Every `Spawned` handle is matched by exactly
+    /// one of these, whatever removed the particle.
+    Removed {
+        handle: ParticleHandle,
+        position: Vec3,
+        cause: RemovalCause,
+    },
+    /// A wall particle broke out of the wall
+    Detached { wall_index: usize },
+    /// A detached wall particle arrived back in its slot
+    Reattached { wall_index: usize },
+    /// A sleeping wall particle was disturbed and is simulated again
+    Woke { wall_index: usize },
+    /// A wall particle came to rest and stopped being simulated
+    Slept { wall_index: usize },
+}
@@ -0,0 +1,333 @@
+use crate::attributes::ParticleKind;
+use crate::input::{BodyJoints, Joint};
+use crate::vector::Vec3;
+
+/// How a field's strength fades with distance from its origin
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Falloff {
+    /// Full strength inside the radius, nothing outside
+    Constant,
+    /// Fades linearly to zero at the radius
+    Linear,
+    /// Fades smoothly to zero at the radius with no kink at either end
+    Smooth,
+    /// Inverse-square fade, softened so it stays finite at the origin, cut off at the radius
+    InverseSquare { softening: f64 },
+}
+
+impl Falloff {
+    /// Weight in `0.0..=1.0` at `distance` for a field of `radius`
+    pub fn weight(&self, distance: f64, radius: f64) -> f64 {
+        if distance >= radius {
+            return 0.0;
+        }
+        match *self {
+            Falloff::Constant => 1.0,
+            Falloff::Linear => 1.0 - distance / radius,
+            Falloff::Smooth => {
+                let t = 1.0 - (distance / radius).powi(2);
+                t * t
+            }
+            Falloff::InverseSquare { softening } => {
+                let s2 = softening * softening;
+                s2 / (distance * distance + s2)
+            }
+        }
+    }
+}
+
+/// Source of acceleration applied to every particle each step
+pub trait ForceField: Send + Sync {
+    /// Acceleration of a particle at `position` moving at `velocity`
+    fn acceleration(&self, position: &Vec3, velocity: &Vec3) -> Vec3;
+
+    /// Move the field, e.g. to follow a body joint. Fields without a location ignore this.
+    fn set_origin(&mut self, _origin: Vec3) {}
+
+    /// Let the field evolve over `dt` seconds; called once per fixed step. Static fields ignore this.
+    fn advance(&mut self, _dt: f64) {}
+}
+
+/// Pulls particles towards a point; a negative strength pushes them away
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointAttractor {
+    pub origin: Vec3,
+    /// Acceleration at full weight, in units/s²
+    pub strength: f64,
+    pub radius: f64,
+    pub falloff: Falloff,
+}
+
+impl PointAttractor {
+    pub fn new(origin: Vec3, strength: f64, radius: f64, falloff: Falloff) -> Self {
+        Self {
+            origin,
+            strength,
+            radius,
+            falloff,
+        }
+    }
+
+    /// A field pushing particles away from `origin`
+    pub fn repeller(origin: Vec3, strength: f64, radius: f64, falloff: Falloff) -> Self {
+        Self::new(origin, -strength, radius, falloff)
+    }
+}
+
+impl ForceField for PointAttractor {
+    fn acceleration(&self, position: &Vec3, _velocity: &Vec3) -> Vec3 {
+        let mut direction = self.origin - *position;
+        let distance = direction.magnitude();
+        if distance <= f64::EPSILON {
+            return Vec3::new();
+        }
+        direction.normalize();
+        direction * (self.strength * self.falloff.weight(distance, self.radius))
+    }
+
+    fn set_origin(&mut self, origin: Vec3) {
+        self.origin = origin;
+    }
+}
+
+/// Swirls particles around an axis through a point
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vortex {
+    pub origin: Vec3,
+    /// Unit axis; positive strength turns counter-clockwise looking down the axis
+    pub axis: Vec3,
+    /// Tangential acceleration at full weight, in units/s²
+    pub strength: f64,
+    /// Extra acceleration towards the axis, in units/s², to keep particles in the swirl
+    pub inward: f64,
+    pub radius: f64,
+    pub falloff: Falloff,
+}
+
+impl Vortex {
+    pub fn new(origin: Vec3, axis: Vec3, strength: f64, radius: f64, falloff: Falloff) -> Self {
+        let mut axis = axis;
+        axis.normalize();
+        Self {
+            origin,
+            axis,
+            strength,
+            inward: 0.0,
+            radius,
+            falloff,
+        }
+    }
+}
+
+impl ForceField for Vortex {
+    fn acceleration(&self, position: &Vec3, _velocity: &Vec3) -> Vec3 {
+        // Offset from the axis, ignoring the component along it
+        let offset = *position - self.origin;
+        let radial = offset - self.axis * offset.dot(&self.axis);
+        let distance = radial.magnitude();
+        if distance <= f64::EPSILON {
+            return Vec3::new();
+        }
+
+        let weight = self.falloff.weight(distance, self.radius);
+        let mut tangent = self.axis.cross(&radial);
+        tangent.normalize();
+        let mut inward = radial * -1.0;
+        inward.normalize();
+
+        tangent * (self.strength * weight) + inward * (self.inward * weight)
+    }
+
+    fn set_origin(&mut self, origin: Vec3) {
+        self.origin = origin;
+    }
+}
+
+/// Uniform wind that drags particles towards its velocity
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wind {
+    /// Velocity of the air, in units/s
+    pub velocity: Vec3,
+    /// How quickly particles match the wind, in 1/s
+    pub drag: f64,
+}
+
+impl Wind {
+    pub fn new(velocity: Vec3, drag: f64) -> Self {
+        Self { velocity, drag }
+    }
+}
+
+impl ForceField for Wind {
+    fn acceleration(&self, _position: &Vec3, velocity: &Vec3) -> Vec3 {
+        (self.velocity - *velocity) * self.drag
+    }
+}
+
+/// When a field attached to a joint is active
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activation {
+    Always,
+    /// Only while the joint is a tracked, open hand
+    HandOpen,
+    /// Only while the joint is a tracked, closed hand
+    HandClosed,
+}
+
+/// Binds a field's origin to a body joint
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JointAttachment {
+    pub joint: Joint,
+    /// Added to the joint position to get the field origin
+    pub offset: Vec3,
+    pub activation: Activation,
+}
+
+impl JointAttachment {
+    pub fn new(joint: Joint, activation: Activation) -> Self {
+        Self {
+            joint,
+            offset: Vec3::new(),
+            activation,
+        }
+    }
+
+    /// Whether the field should act for the current pose
+    pub fn is_active(&self, body: &BodyJoints) -> bool {
+        match (self.activation, body.hand(self.joint)) {
+            (Activation::Always, _) => true,
+            (Activation::HandOpen, Some(hand)) => hand.is_tracked && hand.is_open,
+            (Activation::HandClosed, Some(hand)) => hand.is_tracked && !hand.is_open,
+            (_, None) => false,
+        }
+    }
+}
+
+/// Index of a field registered on `PhysicsSystem`
+pub type FieldId = usize;
+
+/// A registered field together with its optional joint binding
+pub struct FieldSlot {
+    pub field: Box<dyn ForceField>,
+    pub attachment: Option<JointAttachment>,
+    pub enabled: bool,
+    /// Also act on wall particles, not just force particles
+    pub affects_wall: bool,
+}
+
+impl FieldSlot {
+    pub fn new(field: Box<dyn ForceField>) -> Self {
+        Self {
+            field,
+            attachment: None,
+            enabled: true,
+            affects_wall: true,
+        }
+    }
+
+    pub fn applies_to(&self, kind: ParticleKind) -> bool {
+        self.enabled && (kind == ParticleKind::Force || self.affects_wall)
+    }
+}
+
+/// Sum of the accelerations of every enabled field acting on particles of `kind`
+pub fn total_acceleration(fields: &[FieldSlot], kind: ParticleKind, position: &Vec3, velocity: &Vec3) -> Vec3 {
+    let mut acceleration = Vec3::new();
+    for slot in fields.iter().filter(|slot| slot.applies_to(kind)) {
+        acceleration.add(&slot.field.acceleration(position, velocity));
+    }
+    acceleration
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: Vec3, expected: Vec3) {
+        assert!((actual - expected).magnitude() < 1e-12, "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn falloffs_fade_to_zero_at_the_radius() {
+        let radius = 10.0;
+        let cases = [
+            (Falloff::Constant, [1.0, 1.0, 1.0]),
+            (Falloff::Linear, [1.0, 0.8, 0.5]),
+            (Falloff::Smooth, [1.0, 0.9216, 0.5625]),
+            (Falloff::InverseSquare { softening: 2.0 }, [1.0, 0.5, 0.137_931_034_482_758_6]),
+        ];
+        for (falloff, expected) in cases {
+            for (distance, weight) in [0.0, 2.0, 5.0].into_iter().zip(expected) {
+                assert!((falloff.weight(distance, radius) - weight).abs() < 1e-12, "{:?} at {}", falloff, distance);
+            }
+            assert_eq!(falloff.weight(radius, radius), 0.0, "{:?}", falloff);
+            assert_eq!(falloff.weight(2.0 * radius, radius), 0.0, "{:?}", falloff);
+
+            // Never stronger further out
+            let weights: Vec<f64> = (0..=100).map(|i| falloff.weight(i as f64 * 0.1, radius)).collect();
+            assert!(weights.windows(2).all(|pair| pair[1] <= pair[0]), "{:?}", falloff);
+        }
+    }
+
+    #[test]
+    fn point_attractor_pulls_towards_its_origin() {
+        let origin = Vec3::from_coords(1.0, 2.0, 3.0);
+        let mut attractor = PointAttractor::new(origin, 40.0, 10.0, Falloff::Linear);
+        let still = Vec3::new();
+
+        // 5 units along +x: half weight, pointing back at the origin
+        let position = origin + Vec3::from_coords(5.0, 0.0, 0.0);
+        assert_near(attractor.acceleration(&position, &still), Vec3::from_coords(-20.0, 0.0, 0.0));
+        let repeller = PointAttractor::repeller(origin, 40.0, 10.0, Falloff::Linear);
+        assert_near(repeller.acceleration(&position, &still), Vec3::from_coords(20.0, 0.0, 0.0));
+
+        assert_eq!(attractor.acceleration(&origin, &still), Vec3::new());
+        assert_eq!(attractor.acceleration(&(origin + Vec3::from_coords(0.0, 10.0, 0.0)), &still), Vec3::new());
+
+        attractor.set_origin(position);
+        assert_eq!(attractor.acceleration(&position, &still), Vec3::new());
+        assert_near(attractor.acceleration(&origin, &still), Vec3::from_coords(20.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn activation_follows_the_hand() {
+        let mut body = BodyJoints::default();
+        let attachment = |joint, activation| JointAttachment::new(joint, activation);
+        let open = attachment(Joint::RightHand, Activation::HandOpen);
+        let closed = attachment(Joint::RightHand, Activation::HandClosed);
+
+        // An untracked hand is neither open nor closed
+        body.right_hand.is_open = true;
+        assert!(!open.is_active(&body));
+        assert!(!closed.is_active(&body));
+
+        body.right_hand.is_tracked = true;
+        assert!(open.is_active(&body));
+        assert!(!closed.is_active(&body));
+        body.right_hand.is_open = false;
+        assert!(!open.is_active(&body));
+        assert!(closed.is_active(&body));
+
+        // The other hand does not count
+        assert!(!attachment(Joint::LeftHand, Activation::HandClosed).is_active(&body));
+        // Joints that are not hands are only ever active with `Always`
+        assert!(attachment(Joint::Neck, Activation::Always).is_active(&body));
+        assert!(attachment(Joint::LeftHand, Activation::Always).is_active(&body));
+        assert!(!attachment(Joint::Neck, Activation::HandOpen).is_active(&body));
+        assert!(!attachment(Joint::Neck, Activation::HandClosed).is_active(&body));
+    }
+
+    #[test]
+    fn total_acceleration_skips_disabled_and_force_only_fields() {
+        let pull = |x: f64| Box::new(PointAttractor::new(Vec3::from_coords(x, 0.0, 0.0), 10.0, 100.0, Falloff::Constant));
+        let mut fields = vec![FieldSlot::new(pull(10.0)), FieldSlot::new(pull(-10.0)), FieldSlot::new(pull(10.0))];
+        fields[1].enabled = false;
+        fields[2].affects_wall = false;
+
+        let (position, velocity) = (Vec3::new(), Vec3::new());
+        let force = total_acceleration(&fields, ParticleKind::Force, &position, &velocity);
+        assert_near(force, Vec3::from_coords(20.0, 0.0, 0.0));
+        let wall = total_acceleration(&fields, ParticleKind::Wall, &position, &velocity);
+        assert_near(wall, Vec3::from_coords(10.0, 0.0, 0.0));
+    }
+}
@@ -1,5 +1,7 @@
+use crate::clock::{MonotonicClock, SharedClock};
 use crate::vector::Vec3;
 use crate::pose_detection::PoseDetector;
+use std::sync::Arc;
 use winit::event::{MouseButton, ElementState};
 
 #[derive(Debug, Clone)]
@@ -25,6 +27,20 @@ impl Default for HandState {
     }
 }
 
+/// Named joint of the tracked skeleton
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Joint {
+    RightHand,
+    LeftHand,
+    SpineShoulder,
+    SpineBase,
+    ShoulderRight,
+    ShoulderLeft,
+    ElbowRight,
+    ElbowLeft,
+    Neck,
+}
+
 #[derive(Debug, Clone)]
 pub struct BodyJoints {
     pub right_hand: HandState,
@@ -33,9 +49,12 @@ pub struct BodyJoints {
     pub spine_base: Vec3,
     pub shoulder_right: Vec3,
     pub shoulder_left: Vec3,
+    pub elbow_right: Vec3,
+    pub elbow_left: Vec3,
     pub neck: Vec3,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for BodyJoints {
     fn default() -> Self {
         Self {
@@ -45,18 +64,61 @@ impl Default for BodyJoints {
             spine_base: Vec3::new(),
             shoulder_right: Vec3::new(),
             shoulder_left: Vec3::new(),
+            elbow_right: Vec3::new(),
+            elbow_left: Vec3::new(),
             neck: Vec3::new(),
         }
     }
 }
 
+impl BodyJoints {
+    /// Current position of a joint
+    pub fn position(&self, joint: Joint) -> Vec3 {
+        match joint {
+            Joint::RightHand => self.right_hand.position,
+            Joint::LeftHand => self.left_hand.position,
+            Joint::SpineShoulder => self.spine_shoulder,
+            Joint::SpineBase => self.spine_base,
+            Joint::ShoulderRight => self.shoulder_right,
+            Joint::ShoulderLeft => self.shoulder_left,
+            Joint::ElbowRight => self.elbow_right,
+            Joint::ElbowLeft => self.elbow_left,
+            Joint::Neck => self.neck,
+        }
+    }
+
+    /// Hand state for hand joints
+    pub fn hand(&self, joint: Joint) -> Option<&HandState> {
+        match joint {
+            Joint::RightHand => Some(&self.right_hand),
+            Joint::LeftHand => Some(&self.left_hand),
+            _ => None,
+        }
+    }
+}
+
+/// Joints read from the pose detector in one update; `None` where a joint was not found
+struct DetectedPose {
+    right_hand_pos: Option<Vec3>,
+    left_hand_pos: Option<Vec3>,
+    right_hand_open: bool,
+    left_hand_open: bool,
+    neck_pos: Option<Vec3>,
+    right_shoulder_pos: Option<Vec3>,
+    left_shoulder_pos: Option<Vec3>,
+    right_elbow_pos: Option<Vec3>,
+    left_elbow_pos: Option<Vec3>,
+}
+
 pub struct InputSystem {
     pub body_joints: BodyJoints,
     pub is_calibrated: bool,
     pose_detector: Option<PoseDetector>,
     window_size: (u32, u32),
-    last_update_time: u64,
+    clock: SharedClock,
+    last_update_time: Option<u64>,
     // Fallback mouse input for debugging
+    #[allow(dead_code)]
     mouse_position: (f64, f64),
     mouse_pressed: bool,
     use_pose_detection: bool,
@@ -64,19 +126,24 @@ pub struct InputSystem {
 
 impl InputSystem {
     pub fn new(window_width: u32, window_height: u32) -> Self {
+        Self::with_clock(window_width, window_height, Arc::new(MonotonicClock::new()))
+    }
+
+    pub fn with_clock(window_width: u32, window_height: u32, clock: SharedClock) -> Self {
         let mut system = Self {
             body_joints: BodyJoints::default(),
             is_calibrated: false,
             pose_detector: None,
             window_size: (window_width, window_height),
-            last_update_time: 0,
+            clock: clock.clone(),
+            last_update_time: None,
             mouse_position: (0.0, 0.0),
             mouse_pressed: false,
             use_pose_detection: true,
         };
 
         // Try to initialize pose detection
-        match PoseDetector::new() {
+        match PoseDetector::with_clock(clock) {
             Ok(mut detector) => {
                 match detector.initialize_openpose() {
                     Ok(_) => {
@@ -122,31 +189,38 @@ impl InputSystem {
                 let neck_pos = detector.get_body_keypoint("Neck");
                 let right_shoulder_pos = detector.get_body_keypoint("RShoulder");
                 let left_shoulder_pos = detector.get_body_keypoint("LShoulder");
+                let right_elbow_pos = detector.get_body_keypoint("RElbow");
+                let left_elbow_pos = detector.get_body_keypoint("LElbow");
 
                 // Update body joints with the retrieved data
-                self.update_body_joints_from_data(
-                    right_hand_pos, left_hand_pos,
-                    right_hand_open, left_hand_open,
-                    neck_pos, right_shoulder_pos, left_shoulder_pos
-                );
+                self.update_body_joints_from_data(DetectedPose {
+                    right_hand_pos,
+                    left_hand_pos,
+                    right_hand_open,
+                    left_hand_open,
+                    neck_pos,
+                    right_shoulder_pos,
+                    left_shoulder_pos,
+                    right_elbow_pos,
+                    left_elbow_pos,
+                });
             }
         }
     }
 
-    fn update_body_joints_from_data(
-        &mut self,
-        right_hand_pos: Option<Vec3>,
-        left_hand_pos: Option<Vec3>,
-        right_hand_open: bool,
-        left_hand_open: bool,
-        neck_pos: Option<Vec3>,
-        right_shoulder_pos: Option<Vec3>,
-        left_shoulder_pos: Option<Vec3>,
-    ) {
-        let current_time = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64;
+    fn update_body_joints_from_data(&mut self, pose: DetectedPose) {
+        let DetectedPose {
+            right_hand_pos,
+            left_hand_pos,
+            right_hand_open,
+            left_hand_open,
+            neck_pos,
+            right_shoulder_pos,
+            left_shoulder_pos,
+            right_elbow_pos,
+            left_elbow_pos,
+        } = pose;
+        let current_time = self.clock.now_ms();
 
         // Update right hand
         if let Some(right_hand_position) = right_hand_pos {
@@ -156,8 +230,8 @@ impl InputSystem {
             self.body_joints.right_hand.is_open = right_hand_open;
             
             // Calculate velocity
-            if self.last_update_time > 0 {
-                let dt = (current_time - self.last_update_time) as f64 / 1000.0; // seconds
+            if let Some(last_update_time) = self.last_update_time {
+                let dt = current_time.saturating_sub(last_update_time) as f64 / 1000.0; // seconds
                 if dt > 0.0 {
                     let velocity = (self.body_joints.right_hand.position - self.body_joints.right_hand.previous_position) * (1.0 / dt);
                     self.body_joints.right_hand.velocity = velocity;
@@ -175,8 +249,8 @@ impl InputSystem {
             self.body_joints.left_hand.is_open = left_hand_open;
             
             // Calculate velocity
-            if self.last_update_time > 0 {
-                let dt = (current_time - self.last_update_time) as f64 / 1000.0;
+            if let Some(last_update_time) = self.last_update_time {
+                let dt = current_time.saturating_sub(last_update_time) as f64 / 1000.0;
                 if dt > 0.0 {
                     let velocity = (self.body_joints.left_hand.position - self.body_joints.left_hand.previous_position) * (1.0 / dt);
                     self.body_joints.left_hand.velocity = velocity;
@@ -199,13 +273,21 @@ impl InputSystem {
             self.body_joints.shoulder_left = left_shoulder_position;
         }
 
+        if let Some(right_elbow_position) = right_elbow_pos {
+            self.body_joints.elbow_right = right_elbow_position;
+        }
+
+        if let Some(left_elbow_position) = left_elbow_pos {
+            self.body_joints.elbow_left = left_elbow_position;
+        }
+
         // Estimate spine positions based on shoulders and neck
         if self.body_joints.neck.magnitude() > 0.0 {
             self.body_joints.spine_shoulder = self.body_joints.neck + Vec3::from_coords(0.0, 30.0, 0.0);
             self.body_joints.spine_base = self.body_joints.spine_shoulder + Vec3::from_coords(0.0, 150.0, 0.0);
         }
 
-        self.last_update_time = current_time;
+        self.last_update_time = Some(current_time);
     }
 
     // Fallback mouse input methods (for debugging when camera is not available)
@@ -219,6 +301,8 @@ impl InputSystem {
 
             self.body_joints.right_hand.position = Vec3::from_coords(normalized_x, normalized_y, z);
             self.body_joints.right_hand.is_tracked = true;
+            // No elbow to track with a mouse; keep it halfway along the arm
+            self.body_joints.elbow_right = (self.body_joints.shoulder_right + self.body_joints.right_hand.position) * 0.5;
             
             // Calculate velocity for mouse input
             let velocity = self.body_joints.right_hand.position - prev_pos;
@@ -254,11 +338,15 @@ impl InputSystem {
                 }
             }
         } else {
-            // Fallback mouse calibration
-            self.body_joints.spine_base = Vec3::from_coords(0.0, 0.0, 0.0);
-            self.body_joints.spine_shoulder = Vec3::from_coords(0.0, 50.0, 0.0);
-            self.body_joints.shoulder_right = Vec3::from_coords(30.0, 40.0, 0.0);
+            // Fallback mouse calibration; the body stands at the pose detector's depth,
+            // clear of the wall, so its colliders only touch the wall through the hand
+            self.body_joints.spine_base = Vec3::from_coords(0.0, 0.0, -100.0);
+            self.body_joints.spine_shoulder = Vec3::from_coords(0.0, 50.0, -100.0);
+            self.body_joints.shoulder_right = Vec3::from_coords(30.0, 40.0, -100.0);
+            self.body_joints.shoulder_left = Vec3::from_coords(-30.0, 40.0, -100.0);
             self.body_joints.left_hand.position = Vec3::from_coords(-50.0, 0.0, -100.0);
+            self.body_joints.elbow_right = (self.body_joints.shoulder_right + self.body_joints.right_hand.position) * 0.5;
+            self.body_joints.elbow_left = (self.body_joints.shoulder_left + self.body_joints.left_hand.position) * 0.5;
             
             self.is_calibrated = true;
             println!("🖱️  Mouse input calibrated - use mouse to control right hand");
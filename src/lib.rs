@@ -0,0 +1,26 @@
+pub mod attributes;
+pub mod body_collider;
+pub mod boundary;
+pub mod clock;
+pub mod detachment;
+pub mod diagnostics;
+pub mod cloth;
+pub mod collider;
+pub mod curl_noise;
+pub mod vector;
+pub mod particle;
+pub mod particle_store;
+pub mod parallel;
+pub mod physics;
+pub mod spatial_hash;
+pub mod simple_renderer;
+pub mod sleep;
+pub mod electrostatics;
+pub mod emitter;
+pub mod events;
+pub mod force_field;
+pub mod input;
+pub mod integrator;
+pub mod material;
+pub mod pose_detection;
+pub mod wall_grid;
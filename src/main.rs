@@ -1,10 +1,5 @@
-mod vector;
-mod particle;
-mod physics;
-mod simple_renderer;
-mod input;
-mod pose_detection;
-
+use std::sync::Arc;
+use std::time::Duration;
 use winit::{
     event::*,
     event_loop::{EventLoop},
@@ -12,27 +7,53 @@ use winit::{
     dpi::PhysicalSize,
 };
 
-use physics::PhysicsSystem;
-use simple_renderer::Renderer;
-use input::InputSystem;
+use force_it_v2_rust::attributes::{FadeWithAge, ImpactHeating, TemperatureColor, WHITE};
+use force_it_v2_rust::body_collider::BodyColliders;
+use force_it_v2_rust::clock::{Clock, MonotonicClock, ScaledClock};
+use force_it_v2_rust::collider::Plane;
+use force_it_v2_rust::curl_noise::CurlNoise;
+use force_it_v2_rust::detachment::DetachConfig;
+use force_it_v2_rust::electrostatics::ElectrostaticsConfig;
+use force_it_v2_rust::emitter::{Emitter, EmitterConfig, EmitterId, EmitterShape, ValueRange};
+use force_it_v2_rust::force_field::{Activation, Falloff, JointAttachment, PointAttractor};
+use force_it_v2_rust::input::Joint;
+use force_it_v2_rust::material::WALL_MATERIAL;
+use force_it_v2_rust::parallel::Execution;
+use force_it_v2_rust::physics::{PhysicsConfig, PhysicsSystem};
+use force_it_v2_rust::vector::Vec3;
+use force_it_v2_rust::simple_renderer::Renderer;
+use force_it_v2_rust::input::InputSystem;
 
 const SCENE_WIDTH: u32 = 1920;
 const SCENE_HEIGHT: u32 = 1080;
 
 struct App {
+    seed: Option<u64>,
+    stats_path: Option<String>,
+    charged: bool,
     window: Option<Window>,
     physics_system: Option<PhysicsSystem>,
+    hand_emitter: Option<EmitterId>,
     input_system: Option<InputSystem>,
     renderer: Option<Renderer>,
+    clock: Option<Arc<ScaledClock>>,
+    last_frame: Option<Duration>,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for App {
     fn default() -> Self {
         Self {
+            seed: None,
+            stats_path: None,
+            charged: false,
             window: None,
             physics_system: None,
+            hand_emitter: None,
             input_system: None,
             renderer: None,
+            clock: None,
+            last_frame: None,
         }
     }
 }
@@ -87,6 +108,23 @@ impl winit::application::ApplicationHandler for App {
                                     }
                                 }
                             }
+                            winit::keyboard::KeyCode::KeyP if event.state == ElementState::Pressed => {
+                                if let Some(clock) = &self.clock {
+                                    if clock.is_paused() {
+                                        clock.set_scale(1.0);
+                                        println!("Simulation resumed");
+                                    } else {
+                                        clock.set_scale(0.0);
+                                        println!("Simulation paused");
+                                    }
+                                }
+                            }
+                            winit::keyboard::KeyCode::KeyR if event.state == ElementState::Pressed => {
+                                if let Some(physics_system) = &mut self.physics_system {
+                                    physics_system.reset_wall();
+                                    println!("Wall reset");
+                                }
+                            }
                             winit::keyboard::KeyCode::Escape => {
                                 event_loop.exit();
                             }
@@ -97,8 +135,91 @@ impl winit::application::ApplicationHandler for App {
                 WindowEvent::RedrawRequested => {
                     // Initialize systems if needed
                     if self.physics_system.is_none() {
-                        self.physics_system = Some(PhysicsSystem::new());
-                        self.input_system = Some(InputSystem::new(SCENE_WIDTH, SCENE_HEIGHT));
+                        let clock = Arc::new(ScaledClock::new(Arc::new(MonotonicClock::new()), 1.0));
+                        let config = PhysicsConfig {
+                            seed: self.seed,
+                            execution: Execution::Parallel,
+                            // Screen coordinates: +y is down
+                            gravity: Vec3::from_coords(0.0, 400.0, 0.0),
+                            detach: DetachConfig {
+                                displacement: 40.0,
+                                impact_energy: 500.0,
+                                ..DetachConfig::default()
+                            },
+                            electrostatics: ElectrostaticsConfig {
+                                enabled: self.charged,
+                                ..ElectrostaticsConfig::default()
+                            },
+                            ..PhysicsConfig::default()
+                        };
+                        let mut physics_system = PhysicsSystem::with_clock(config, clock.clone());
+                        println!("Session seed: {} (replay with --seed {})", physics_system.seed(), physics_system.seed());
+                        if let Some(path) = &self.stats_path {
+                            match physics_system.stream_stats_to(path) {
+                                Ok(()) => println!("Streaming physics stats to {}", path),
+                                Err(err) => eprintln!("Cannot write physics stats to {}: {}", path, err),
+                            }
+                        }
+
+                        // A closed left hand draws the wall in, an open right hand pushes it away
+                        physics_system.add_attached_field(
+                            Box::new(PointAttractor::new(Vec3::new(), 120.0, 150.0, Falloff::Smooth)),
+                            JointAttachment::new(Joint::LeftHand, Activation::HandClosed),
+                        );
+                        physics_system.add_attached_field(
+                            Box::new(PointAttractor::repeller(Vec3::new(), 120.0, 120.0, Falloff::Smooth)),
+                            JointAttachment::new(Joint::RightHand, Activation::HandOpen),
+                        );
+
+                        // The open right hand sprays positively charged force particles along its motion
+                        let hand_emitter = physics_system.add_emitter(Emitter::new(EmitterConfig {
+                            attachment: Some(JointAttachment::new(Joint::RightHand, Activation::HandOpen)),
+                            charge: ValueRange::constant(1.0),
+                            ..EmitterConfig::default()
+                        }));
+
+                        // With charges on, the open left hand sprays negative particles that the
+                        // slightly positive wall draws in, while the right hand's are pushed off
+                        if self.charged {
+                            physics_system.add_emitter(Emitter::new(EmitterConfig {
+                                attachment: Some(JointAttachment::new(Joint::LeftHand, Activation::HandOpen)),
+                                charge: ValueRange::constant(-1.0),
+                                ..EmitterConfig::default()
+                            }));
+                            physics_system.configure_wall(|_, _, particle| particle.attributes.charge = 0.2);
+                        }
+
+                        // Slowly churning turbulence carries the force particles off like smoke; the wall keeps still
+                        let turbulence = physics_system.add_field(Box::new(CurlNoise::new(physics_system.seed(), 0.01, 60.0, 3)));
+                        if let Some(slot) = physics_system.field_mut(turbulence) {
+                            slot.affects_wall = false;
+                        }
+
+                        // Floor just below the wall for knocked-out pieces to pile up on
+                        physics_system.add_collider(
+                            Box::new(Plane::through(Vec3::new(), Vec3::from_coords(0.0, -1.0, 0.0))),
+                            WALL_MATERIAL,
+                        );
+
+                        // Arms and torso push into the wall alongside the emitted particles
+                        physics_system.set_body_colliders(BodyColliders::default());
+
+                        // Force particles fade out as they age; struck wall particles glow and cool down
+                        physics_system.add_attribute_rule(Box::new(FadeWithAge::new(
+                            [1.0, 0.8, 0.3, 1.0],
+                            [1.0, 0.2, 0.0, 0.0],
+                        )));
+                        physics_system.add_attribute_rule(Box::new(ImpactHeating::new(0.01, 1.5)));
+                        physics_system.add_attribute_rule(Box::new(TemperatureColor {
+                            cold: WHITE,
+                            hot: [1.0, 0.3, 0.1, 1.0],
+                            max: 20.0,
+                        }));
+
+                        self.physics_system = Some(physics_system);
+                        self.hand_emitter = Some(hand_emitter);
+                        self.input_system = Some(InputSystem::with_clock(SCENE_WIDTH, SCENE_HEIGHT, clock.clone()));
+                        self.clock = Some(clock);
                         
                         // Initialize renderer asynchronously
                         if self.renderer.is_none() {
@@ -108,24 +229,27 @@ impl winit::application::ApplicationHandler for App {
                     }
 
                     // Update
+                    let now = self.clock.as_ref().map(|clock| clock.now()).unwrap_or_default();
+                    let frame_dt = self
+                        .last_frame
+                        .map(|last| now.saturating_sub(last).as_secs_f64())
+                        .unwrap_or(0.0);
+                    self.last_frame = Some(now);
+
                     if let Some(physics_system) = &mut self.physics_system {
-                        physics_system.update();
+                        physics_system.update(frame_dt);
 
                         // Update input system
                         if let Some(input_system) = &mut self.input_system {
                             input_system.update();
                             
                             if input_system.is_calibrated {
-                                let right_hand_vector = input_system.get_right_hand_vector();
-                                if right_hand_vector.magnitude() > 0.001 && input_system.body_joints.right_hand.is_open {
-                                    let hand_position = input_system.body_joints.right_hand.position;
-                                    let spread_distance = input_system.get_spread_distance();
-                                    
-                                    physics_system.create_force_particles(
-                                        &hand_position,
-                                        &right_hand_vector,
-                                        spread_distance,
-                                    );
+                                physics_system.update_attachments(&input_system.body_joints);
+
+                                // The left hand's distance from the spine widens the spray
+                                let spread_distance = input_system.get_spread_distance();
+                                if let Some(emitter) = self.hand_emitter.and_then(|id| physics_system.emitter_mut(id)) {
+                                    emitter.config.shape = EmitterShape::Sphere { radius: spread_distance * 0.025 };
                                 }
                             }
                         }
@@ -133,9 +257,7 @@ impl winit::application::ApplicationHandler for App {
 
                     // Render
                     if let (Some(renderer), Some(physics_system)) = (&mut self.renderer, &self.physics_system) {
-                        let particles = physics_system.get_all_particles();
-                        let particle_refs: Vec<_> = particles.into_iter().cloned().collect();
-                        match renderer.render(&particle_refs) {
+                        match renderer.render(&physics_system.wall_particles, &physics_system.force_particles) {
                             Ok(_) => {}
                             Err(e) => eprintln!("Render error: {:?}", e),
                         }
@@ -154,11 +276,18 @@ async fn main() {
     env_logger::init();
 
     let event_loop = EventLoop::new().unwrap();
-    let mut app = App::default();
+    let mut app = App {
+        seed: parse_seed_arg(std::env::args()),
+        stats_path: parse_arg(std::env::args(), "--stats"),
+        charged: std::env::args().any(|arg| arg == "--charged"),
+        ..App::default()
+    };
     
     println!("ForceIt - AI-Powered Pose Interaction System");
     println!("Controls:");
     println!("  Space - Calibrate system");
+    println!("  P - Pause/resume simulation");
+    println!("  R - Reset wall");
     println!("  📸 Camera - Real-time pose detection");
     println!("  ✋ Hand Gestures - Control particles with your hands");
     println!("  🖱️  Mouse - Fallback input (if camera unavailable)");
@@ -167,3 +296,32 @@ async fn main() {
 
     event_loop.run_app(&mut app).unwrap();
 }
+
+/// Read `--seed <n>` or `--seed=<n>` from the command line
+fn parse_seed_arg(args: impl Iterator<Item = String>) -> Option<u64> {
+    let value = parse_arg(args, "--seed")?;
+    match value.parse() {
+        Ok(seed) => Some(seed),
+        Err(_) => {
+            eprintln!("Ignoring invalid seed: {}", value);
+            None
+        }
+    }
+}
+
+/// Read `<name> <value>` or `<name>=<value>` from the command line
+fn parse_arg(args: impl Iterator<Item = String>, name: &str) -> Option<String> {
+    let prefix = format!("{}=", name);
+    let mut args = args.skip(1);
+    while let Some(arg) = args.next() {
+        let value = if arg == name {
+            args.next()
+        } else {
+            arg.strip_prefix(&prefix).map(str::to_string)
+        };
+        if value.is_some() {
+            return value;
+        }
+    }
+    None
+}
@@ -0,0 +1,85 @@
+/// Surface properties used when two particles collide
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Material {
+    /// Fraction of the normal approach speed kept after impact (0 = inelastic, 1 = elastic)
+    pub restitution: f64,
+    /// Coulomb friction coefficient along the contact tangent
+    pub friction: f64,
+}
+
+impl Material {
+    pub fn new(restitution: f64, friction: f64) -> Self {
+        Self {
+            restitution: restitution.clamp(0.0, 1.0),
+            friction: friction.max(0.0),
+        }
+    }
+
+    /// Restitution and friction for a contact between two materials.
+    /// The bouncier surface wins; friction is the geometric mean.
+    pub fn combine(&self, other: &Material) -> (f64, f64) {
+        (
+            self.restitution.max(other.restitution),
+            (self.friction * other.friction).sqrt(),
+        )
+    }
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self::new(0.5, 0.2)
+    }
+}
+
+/// Index of a material in a `MaterialTable`
+pub type MaterialId = usize;
+
+/// Material used by wall particles unless configured otherwise
+pub const WALL_MATERIAL: MaterialId = 0;
+
+/// Material used by force particles unless configured otherwise
+pub const FORCE_MATERIAL: MaterialId = 1;
+
+/// Materials available to the simulation, looked up by `MaterialId`
+#[derive(Debug, Clone)]
+pub struct MaterialTable {
+    materials: Vec<Material>,
+}
+
+impl MaterialTable {
+    pub fn new() -> Self {
+        let mut table = Self { materials: Vec::new() };
+        table.add(Material::default()); // WALL_MATERIAL
+        table.add(Material::default()); // FORCE_MATERIAL
+        table
+    }
+
+    /// Register a material and return its id
+    pub fn add(&mut self, material: Material) -> MaterialId {
+        self.materials.push(material);
+        self.materials.len() - 1
+    }
+
+    /// Look up a material; unknown ids fall back to the default material
+    pub fn get(&self, id: MaterialId) -> Material {
+        self.materials.get(id).copied().unwrap_or_default()
+    }
+
+    pub fn get_mut(&mut self, id: MaterialId) -> Option<&mut Material> {
+        self.materials.get_mut(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.materials.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.materials.is_empty()
+    }
+}
+
+impl Default for MaterialTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
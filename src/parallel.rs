@@ -0,0 +1,85 @@
+use rayon::prelude::*;
+
+/// Whether the per-particle loops of a step run on one thread or on the rayon pool.
+/// Both produce bit-identical results: every loop writes each element independently
+/// and any sums are taken in a fixed order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Execution {
+    SingleThreaded,
+    Parallel,
+}
+
+/// Items handed to one rayon task; keeps tiny loops from being split into useless jobs
+pub const MIN_PARALLEL_CHUNK: usize = 256;
+
+impl Execution {
+    /// Run `f(index, item)` on every element of `items`
+    pub fn for_each_mut<T, F>(self, items: &mut [T], f: F)
+    where
+        T: Send,
+        F: Fn(usize, &mut T) + Sync + Send,
+    {
+        match self {
+            Execution::SingleThreaded => items.iter_mut().enumerate().for_each(|(i, item)| f(i, item)),
+            Execution::Parallel => items
+                .par_iter_mut()
+                .with_min_len(MIN_PARALLEL_CHUNK)
+                .enumerate()
+                .for_each(|(i, item)| f(i, item)),
+        }
+    }
+
+    /// Run `f(index, a, b)` on matching elements of two slices of the same length
+    pub fn for_each_pair_mut<A, B, F>(self, a: &mut [A], b: &mut [B], f: F)
+    where
+        A: Send,
+        B: Send,
+        F: Fn(usize, &mut A, &mut B) + Sync + Send,
+    {
+        match self {
+            Execution::SingleThreaded => a
+                .iter_mut()
+                .zip(b.iter_mut())
+                .enumerate()
+                .for_each(|(i, (a, b))| f(i, a, b)),
+            Execution::Parallel => a
+                .par_iter_mut()
+                .zip(b.par_iter_mut())
+                .with_min_len(MIN_PARALLEL_CHUNK)
+                .enumerate()
+                .for_each(|(i, (a, b))| f(i, a, b)),
+        }
+    }
+
+    /// Collect `f(index)` for every index in `0..len`, in index order
+    pub fn map<R, F>(self, len: usize, f: F) -> Vec<R>
+    where
+        R: Send,
+        F: Fn(usize) -> R + Sync + Send,
+    {
+        match self {
+            Execution::SingleThreaded => (0..len).map(f).collect(),
+            Execution::Parallel => (0..len).into_par_iter().with_min_len(MIN_PARALLEL_CHUNK).map(f).collect(),
+        }
+    }
+
+    /// Like `map`, with scratch state from `init` reused across calls on the same thread
+    pub fn map_with_scratch<S, R, I, F>(self, len: usize, init: I, f: F) -> Vec<R>
+    where
+        R: Send,
+        I: Fn() -> S + Sync + Send,
+        F: Fn(&mut S, usize) -> R + Sync + Send,
+    {
+        match self {
+            Execution::SingleThreaded => {
+                let mut scratch = init();
+                (0..len).map(|i| f(&mut scratch, i)).collect()
+            }
+            Execution::Parallel => (0..len)
+                .into_par_iter()
+                .with_min_len(MIN_PARALLEL_CHUNK)
+                .map_init(init, f)
+                .collect(),
+        }
+    }
+}
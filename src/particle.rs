@@ -1,5 +1,22 @@
+use crate::attributes::ParticleAttributes;
+use crate::material::{MaterialId, WALL_MATERIAL};
+use crate::collider::Contact;
 use crate::vector::Vec3;
-use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Stiffness of the spring pulling a wall particle back to its rest position, in 1/s².
+/// Equivalent to the original 0.0005 per frame² at 60 Hz.
+pub const GRAVITY_STIFFNESS: f64 = 1.8;
+
+/// Stiffness of the surrounding module's pull on a wall particle, in 1/s². The acceleration
+/// is this times the mean displacement of the attached neighbors within `d` cells, taken
+/// relative to the particle's own displacement.
+pub const MODULE_STIFFNESS: f64 = 3600.0;
+
+/// Overlap tolerated before positional correction kicks in
+const CONTACT_SLOP: f64 = 0.01;
+
+/// Fraction of the remaining overlap removed per contact
+const CONTACT_CORRECTION: f64 = 0.8;
 
 /// Particle struct representing a physics-enabled sphere
 #[derive(Debug, Clone)]
@@ -7,9 +24,18 @@ pub struct Particle {
     pub position: Vec3,
     pub velocity: Vec3,
     pub radius: f64,
+    pub mass: f64,
+    /// Zero for pinned particles, which never move
+    pub inv_mass: f64,
+    /// Linear velocity damping, in 1/s
+    pub damping: f64,
+    pub material: MaterialId,
+    /// Simulation time in milliseconds at which the particle was spawned
     pub created_time: u64,
+    /// How long the particle lives, in milliseconds
+    pub lifetime_ms: u64,
     pub delete_flag: bool,
-    diff: Vec3,
+    pub attributes: ParticleAttributes,
     original_position: Vec3,
 }
 
@@ -21,12 +47,14 @@ impl Particle {
             position,
             velocity: Vec3::new(),
             radius: 1.0,
-            created_time: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_millis() as u64,
+            mass: 1.0,
+            inv_mass: 1.0,
+            damping: 0.0,
+            material: WALL_MATERIAL,
+            created_time: 0,
+            lifetime_ms: u64::MAX,
             delete_flag: false,
-            diff: Vec3::new(),
+            attributes: ParticleAttributes::default(),
             original_position: position,
         }
     }
@@ -36,37 +64,40 @@ impl Particle {
         self.radius = radius;
     }
 
-    /// Move the particle by its velocity
-    pub fn move_particle(&mut self) {
-        self.position.add(&self.velocity);
-        self.diff.reset();
+    /// Set the mass of the particle; a non-finite or non-positive mass pins it in place
+    pub fn set_mass(&mut self, mass: f64) {
+        self.mass = mass;
+        self.inv_mass = if mass.is_finite() && mass > 0.0 { 1.0 / mass } else { 0.0 };
     }
 
-    /// Apply gravity force towards original position
-    pub fn gravity(&mut self) {
-        if self.position.z < 0.0 {
-            // Reset to original position if below ground
-            self.position.copy(&self.original_position);
-            self.velocity.reset();
-        } else {
-            // Apply spring force towards original position
-            let mut force = Vec3::new();
-            force.sub(&self.original_position, &self.position);
-            force.mult(0.0005);
-            self.velocity.add(&force);
-        }
+    /// Make the particle immovable
+    pub fn pin(&mut self) {
+        self.inv_mass = 0.0;
+        self.velocity.reset();
+    }
+
+    pub fn is_pinned(&self) -> bool {
+        self.inv_mass == 0.0
+    }
+
+    /// Move the particle by its velocity over `dt` seconds
+    pub fn move_particle(&mut self, dt: f64) {
+        self.position.add(&(self.velocity * dt));
     }
 
-    /// Set around module for inter-particle forces
-    pub fn set_around_module(&mut self, v: &Vec3) {
-        self.diff.add(v);
+    /// Spring acceleration towards the original position for a particle at `position`.
+    /// Takes the position explicitly so integrators can evaluate intermediate states.
+    pub fn gravity_acceleration(&self, position: &Vec3, stiffness: f64) -> Vec3 {
+        let mut force = Vec3::new();
+        force.sub(&self.original_position, position);
+        force.mult(stiffness);
+        force
     }
 
-    /// Apply module gravity based on surrounding particles
-    pub fn module_gravity(&mut self, d: i32) {
-        let a = 1.0 / (4.0 * d as f64 * (d as f64 + 1.0));
-        self.diff.mult(a);
-        self.velocity.add(&self.diff);
+    /// Put the particle back at rest in its original position
+    pub fn reset_to_original(&mut self) {
+        self.position.copy(&self.original_position);
+        self.velocity.reset();
     }
 
     /// Add velocity to the particle
@@ -79,45 +110,124 @@ impl Particle {
         &self.position
     }
 
+    /// Get the rest position the particle is pulled back to
+    pub fn get_original_position(&self) -> &Vec3 {
+        &self.original_position
+    }
+
+    /// Offset of the particle from its rest position
+    pub fn displacement(&self) -> Vec3 {
+        self.position - self.original_position
+    }
+
     /// Get current velocity
     pub fn get_velocity(&self) -> &Vec3 {
         &self.velocity
     }
 
-    /// Check if particle has exceeded lifetime
-    pub fn is_expired(&self, max_lifetime_ms: u64) -> bool {
-        let current_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64;
-        current_time - self.created_time > max_lifetime_ms
-    }
-
     /// Check collision with another particle
     pub fn collides_with(&self, other: &Particle) -> bool {
-        let dx = self.position.x - other.position.x;
-        let dy = self.position.y - other.position.y;
-        let dz = self.position.z - other.position.z;
+        self.overlaps_sphere(&other.position, other.radius)
+    }
+
+    /// Check whether this particle overlaps the sphere at `center` with `radius`
+    pub fn overlaps_sphere(&self, center: &Vec3, radius: f64) -> bool {
+        let dx = self.position.x - center.x;
+        let dy = self.position.y - center.y;
+        let dz = self.position.z - center.z;
         let distance_squared = dx * dx + dy * dy + dz * dz;
-        let radius_sum = self.radius + other.radius;
+        let radius_sum = self.radius + radius;
         distance_squared < radius_sum * radius_sum
     }
 
-    /// Apply collision response with another particle
-    pub fn apply_collision(&mut self, other: &Particle, m1: f64, m2: f64) {
-        let a = 1.0 / (m1 + m2);
-        
-        let mut v1 = Vec3::new();
-        v1.copy(&self.velocity);
-        v1.mult(m1 - m2);
-        
-        let mut v2 = Vec3::new();
-        v2.copy(&other.velocity);
-        v2.mult(2.0 * m2);
-        
-        v1.add(&v2);
-        v1.mult(a);
-        
-        self.velocity.add(&v1);
+    /// Resolve a contact with another particle by exchanging an impulse along the contact
+    /// normal, with Coulomb friction along the tangent, then pushing both apart to remove overlap.
+    /// Pinned particles are immovable. Returns the normal impulse magnitude.
+    pub fn resolve_collision(&mut self, other: &mut Particle, restitution: f64, friction: f64) -> f64 {
+        let inv_mass = self.inv_mass;
+        let other_inv_mass = other.inv_mass;
+        let inv_mass_sum = inv_mass + other_inv_mass;
+        if inv_mass_sum <= 0.0 {
+            return 0.0;
+        }
+
+        let offset = other.position - self.position;
+        let distance = offset.magnitude();
+        let mut normal = if distance > f64::EPSILON {
+            offset * (1.0 / distance)
+        } else {
+            // Concentric: fall back to the direction of approach
+            self.velocity - other.velocity
+        };
+        normal.normalize();
+        if normal.mag_squared() == 0.0 {
+            normal = Vec3::from_coords(0.0, 0.0, 1.0);
+        }
+
+        // Normal impulse, only while the bodies are approaching
+        let relative_velocity = other.velocity - self.velocity;
+        let normal_speed = relative_velocity.dot(&normal);
+        let mut impulse = 0.0;
+        if normal_speed < 0.0 {
+            impulse = -(1.0 + restitution) * normal_speed / inv_mass_sum;
+            self.velocity.add(&(normal * (-impulse * inv_mass)));
+            other.velocity.add(&(normal * (impulse * other_inv_mass)));
+
+            // Friction impulse, capped by the Coulomb cone
+            let relative_velocity = other.velocity - self.velocity;
+            let mut tangent = relative_velocity - normal * relative_velocity.dot(&normal);
+            if tangent.mag_squared() > f64::EPSILON {
+                tangent.normalize();
+                let tangent_impulse = (-relative_velocity.dot(&tangent) / inv_mass_sum)
+                    .clamp(-friction * impulse, friction * impulse);
+                self.velocity.add(&(tangent * (-tangent_impulse * inv_mass)));
+                other.velocity.add(&(tangent * (tangent_impulse * other_inv_mass)));
+            }
+        }
+
+        // Positional correction, shared by inverse mass
+        let penetration = self.radius + other.radius - distance;
+        let correction = (penetration - CONTACT_SLOP).max(0.0) * CONTACT_CORRECTION / inv_mass_sum;
+        if correction > 0.0 {
+            self.position.add(&(normal * (-correction * inv_mass)));
+            other.position.add(&(normal * (correction * other_inv_mass)));
+        }
+
+        impulse
+    }
+
+    /// Bounce off an immovable surface, e.g. a collider, whose surface moves at `surface_velocity`.
+    /// Same response as `resolve_collision` against a body of infinite mass; returns the
+    /// normal impulse. Pinned particles are left alone.
+    pub fn resolve_contact(&mut self, contact: &Contact, surface_velocity: &Vec3, restitution: f64, friction: f64) -> f64 {
+        if self.inv_mass <= 0.0 {
+            return 0.0;
+        }
+        let normal = contact.normal;
+
+        let relative_velocity = self.velocity - *surface_velocity;
+        let normal_speed = relative_velocity.dot(&normal);
+        let mut impulse = 0.0;
+        if normal_speed < 0.0 {
+            impulse = -(1.0 + restitution) * normal_speed / self.inv_mass;
+            self.velocity.add(&(normal * (impulse * self.inv_mass)));
+
+            let relative_velocity = self.velocity - *surface_velocity;
+            let mut tangent = relative_velocity - normal * relative_velocity.dot(&normal);
+            if tangent.mag_squared() > f64::EPSILON {
+                tangent.normalize();
+                let tangent_impulse = (-relative_velocity.dot(&tangent) / self.inv_mass)
+                    .clamp(-friction * impulse, friction * impulse);
+                self.velocity.add(&(tangent * (tangent_impulse * self.inv_mass)));
+            }
+        }
+
+        // The surface cannot give way, so the particle takes the whole correction
+        let correction = (contact.depth - CONTACT_SLOP).max(0.0) * CONTACT_CORRECTION;
+        if correction > 0.0 {
+            self.position.add(&(normal * correction));
+        }
+
+        impulse
     }
 }
@@ -0,0 +1,452 @@
+use crate::attributes::ParticleAttributes;
+use crate::material::MaterialId;
+use crate::particle::Particle;
+use crate::vector::Vec3;
+use std::collections::VecDeque;
+
+/// Stable reference to a particle in a `ParticleStore`.
+/// Stays valid while the particle lives, even as others are removed around it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParticleHandle {
+    slot: u32,
+    generation: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    /// Position in the dense columns while occupied
+    dense: u32,
+    /// Bumped every time the slot is freed, invalidating old handles
+    generation: u32,
+}
+
+/// Mutable views of every column at once, so separate columns can be borrowed together
+pub struct ParticleColumnsMut<'a> {
+    pub position: &'a mut [Vec3],
+    pub velocity: &'a mut [Vec3],
+    pub radius: &'a mut [f64],
+    pub mass: &'a mut [f64],
+    pub inv_mass: &'a mut [f64],
+    pub damping: &'a mut [f64],
+    pub material: &'a mut [MaterialId],
+    pub created_time: &'a mut [u64],
+    pub lifetime_ms: &'a mut [u64],
+    pub attributes: &'a mut [ParticleAttributes],
+}
+
+/// Struct-of-arrays particle storage.
+/// Live particles are packed densely, so every column is a plain slice; removal swaps the
+/// last particle into the hole. Handles go through a slot table whose freed entries are reused.
+/// A queue of handles in push order lets the oldest particles be found without a search.
+#[derive(Debug, Clone, Default)]
+pub struct ParticleStore {
+    position: Vec<Vec3>,
+    velocity: Vec<Vec3>,
+    radius: Vec<f64>,
+    mass: Vec<f64>,
+    inv_mass: Vec<f64>,
+    damping: Vec<f64>,
+    material: Vec<MaterialId>,
+    created_time: Vec<u64>,
+    lifetime_ms: Vec<u64>,
+    attributes: Vec<ParticleAttributes>,
+    dense_to_slot: Vec<u32>,
+    slots: Vec<Slot>,
+    free_slots: Vec<u32>,
+    /// Handles in push order. Entries of removed particles go stale and are skipped, and
+    /// pruned once they outnumber the live particles.
+    push_order: VecDeque<ParticleHandle>,
+    scratch_indices: Vec<usize>,
+}
+
+impl ParticleStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut store = Self::new();
+        store.reserve(capacity);
+        store
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        self.position.reserve(additional);
+        self.velocity.reserve(additional);
+        self.radius.reserve(additional);
+        self.mass.reserve(additional);
+        self.inv_mass.reserve(additional);
+        self.damping.reserve(additional);
+        self.material.reserve(additional);
+        self.created_time.reserve(additional);
+        self.lifetime_ms.reserve(additional);
+        self.attributes.reserve(additional);
+        self.dense_to_slot.reserve(additional);
+    }
+
+    pub fn len(&self) -> usize {
+        self.position.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.position.is_empty()
+    }
+
+    /// Add a particle, reusing a free slot if there is one
+    pub fn push(&mut self, particle: Particle) -> ParticleHandle {
+        let dense = self.len() as u32;
+        let slot = match self.free_slots.pop() {
+            Some(slot) => {
+                self.slots[slot as usize].dense = dense;
+                slot
+            }
+            None => {
+                self.slots.push(Slot { dense, generation: 0 });
+                (self.slots.len() - 1) as u32
+            }
+        };
+
+        self.position.push(particle.position);
+        self.velocity.push(particle.velocity);
+        self.radius.push(particle.radius);
+        self.mass.push(particle.mass);
+        self.inv_mass.push(particle.inv_mass);
+        self.damping.push(particle.damping);
+        self.material.push(particle.material);
+        self.created_time.push(particle.created_time);
+        self.lifetime_ms.push(particle.lifetime_ms);
+        self.attributes.push(particle.attributes);
+        self.dense_to_slot.push(slot);
+
+        let handle = ParticleHandle {
+            slot,
+            generation: self.slots[slot as usize].generation,
+        };
+        if self.push_order.len() > 2 * self.len() + PUSH_ORDER_SLACK {
+            let slots = &self.slots;
+            self.push_order
+                .retain(|handle| slots[handle.slot as usize].generation == handle.generation);
+        }
+        self.push_order.push_back(handle);
+        handle
+    }
+
+    /// Remove the particle at dense `index`, moving the last particle into its place
+    pub fn swap_remove(&mut self, index: usize) {
+        let last = self.len() - 1;
+        let freed_slot = self.dense_to_slot[index];
+
+        self.position.swap_remove(index);
+        self.velocity.swap_remove(index);
+        self.radius.swap_remove(index);
+        self.mass.swap_remove(index);
+        self.inv_mass.swap_remove(index);
+        self.damping.swap_remove(index);
+        self.material.swap_remove(index);
+        self.created_time.swap_remove(index);
+        self.lifetime_ms.swap_remove(index);
+        self.attributes.swap_remove(index);
+        self.dense_to_slot.swap_remove(index);
+
+        if index != last {
+            let moved_slot = self.dense_to_slot[index];
+            self.slots[moved_slot as usize].dense = index as u32;
+        }
+
+        let slot = &mut self.slots[freed_slot as usize];
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free_slots.push(freed_slot);
+    }
+
+    /// Remove the particle behind `handle`; returns false if it is already gone
+    pub fn remove(&mut self, handle: ParticleHandle) -> bool {
+        match self.index_of(handle) {
+            Some(index) => {
+                self.swap_remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove every particle at the given dense indices, which must be distinct
+    pub fn remove_indices(&mut self, indices: &mut [usize]) {
+        // Highest first, so each swap only pulls in particles that are staying
+        indices.sort_unstable_by(|a, b| b.cmp(a));
+        for &index in indices.iter() {
+            self.swap_remove(index);
+        }
+    }
+
+    /// Remove the `count` particles pushed earliest, calling `on_remove(store, index)` on each
+    /// before any is removed
+    pub fn remove_oldest(&mut self, count: usize, mut on_remove: impl FnMut(&ParticleStore, usize)) {
+        let mut indices = std::mem::take(&mut self.scratch_indices);
+        indices.clear();
+        while indices.len() < count {
+            match self.push_order.pop_front() {
+                Some(handle) => indices.extend(self.index_of(handle)),
+                None => break,
+            }
+        }
+        for &index in &indices {
+            on_remove(self, index);
+        }
+        self.remove_indices(&mut indices);
+        self.scratch_indices = indices;
+    }
+
+    /// Keep only the particles for which `keep(store, index)` is true
+    pub fn retain(&mut self, mut keep: impl FnMut(&ParticleStore, usize) -> bool) {
+        let mut index = 0;
+        while index < self.len() {
+            if keep(self, index) {
+                index += 1;
+            } else {
+                self.swap_remove(index);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        while !self.is_empty() {
+            self.swap_remove(self.len() - 1);
+        }
+        self.push_order.clear();
+    }
+
+    /// Current dense index of a live particle
+    pub fn index_of(&self, handle: ParticleHandle) -> Option<usize> {
+        let slot = self.slots.get(handle.slot as usize)?;
+        (slot.generation == handle.generation).then_some(slot.dense as usize)
+    }
+
+    /// Handle of the particle at dense `index`
+    pub fn handle(&self, index: usize) -> ParticleHandle {
+        let slot = self.dense_to_slot[index];
+        ParticleHandle {
+            slot,
+            generation: self.slots[slot as usize].generation,
+        }
+    }
+
+    /// Copy of the particle at dense `index`.
+    /// Force particles have no rest position, so the copy's original position is its current one.
+    pub fn get(&self, index: usize) -> Particle {
+        let position = self.position[index];
+        let mut particle = Particle::new(position.x, position.y, position.z);
+        particle.velocity = self.velocity[index];
+        particle.radius = self.radius[index];
+        particle.mass = self.mass[index];
+        particle.inv_mass = self.inv_mass[index];
+        particle.damping = self.damping[index];
+        particle.material = self.material[index];
+        particle.created_time = self.created_time[index];
+        particle.lifetime_ms = self.lifetime_ms[index];
+        particle.attributes = self.attributes[index];
+        particle
+    }
+
+    /// Write back the position and velocity of `particle` to dense `index`
+    pub fn set_kinematics(&mut self, index: usize, particle: &Particle) {
+        self.position[index] = particle.position;
+        self.velocity[index] = particle.velocity;
+    }
+
+    /// Copies of every live particle, in storage order
+    pub fn iter(&self) -> impl Iterator<Item = Particle> + '_ {
+        (0..self.len()).map(move |index| self.get(index))
+    }
+
+    pub fn positions(&self) -> &[Vec3] {
+        &self.position
+    }
+
+    pub fn velocities(&self) -> &[Vec3] {
+        &self.velocity
+    }
+
+    pub fn radii(&self) -> &[f64] {
+        &self.radius
+    }
+
+    pub fn masses(&self) -> &[f64] {
+        &self.mass
+    }
+
+    pub fn inv_masses(&self) -> &[f64] {
+        &self.inv_mass
+    }
+
+    pub fn dampings(&self) -> &[f64] {
+        &self.damping
+    }
+
+    pub fn materials(&self) -> &[MaterialId] {
+        &self.material
+    }
+
+    pub fn created_times(&self) -> &[u64] {
+        &self.created_time
+    }
+
+    pub fn lifetimes_ms(&self) -> &[u64] {
+        &self.lifetime_ms
+    }
+
+    pub fn attributes(&self) -> &[ParticleAttributes] {
+        &self.attributes
+    }
+
+    pub fn columns_mut(&mut self) -> ParticleColumnsMut<'_> {
+        ParticleColumnsMut {
+            position: &mut self.position,
+            velocity: &mut self.velocity,
+            radius: &mut self.radius,
+            mass: &mut self.mass,
+            inv_mass: &mut self.inv_mass,
+            damping: &mut self.damping,
+            material: &mut self.material,
+            created_time: &mut self.created_time,
+            lifetime_ms: &mut self.lifetime_ms,
+            attributes: &mut self.attributes,
+        }
+    }
+}
+
+/// Stale push-order entries tolerated on top of twice the live count before pruning
+const PUSH_ORDER_SLACK: usize = 1024;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Particle tagged with `tag` in both its x coordinate and its user data
+    fn numbered(tag: u64) -> Particle {
+        let mut particle = Particle::new(tag as f64, 0.0, 0.0);
+        particle.attributes.user_data = tag;
+        particle
+    }
+
+    /// Store of `count` particles, each tagged with its push order
+    fn numbered_store(count: usize) -> (ParticleStore, Vec<ParticleHandle>) {
+        let mut store = ParticleStore::new();
+        let handles = (0..count).map(|i| store.push(numbered(i as u64))).collect();
+        (store, handles)
+    }
+
+    /// Push-order tags of the live particles, sorted
+    fn tags(store: &ParticleStore) -> Vec<u64> {
+        let mut tags: Vec<u64> = store.attributes().iter().map(|a| a.user_data).collect();
+        tags.sort_unstable();
+        tags
+    }
+
+    /// Every live handle maps to its own index and to the particle it was issued for
+    fn assert_consistent(store: &ParticleStore) {
+        for index in 0..store.len() {
+            let handle = store.handle(index);
+            assert_eq!(store.index_of(handle), Some(index));
+            assert_eq!(store.positions()[index].x, store.attributes()[index].user_data as f64);
+        }
+    }
+
+    #[test]
+    fn stale_handles_stop_resolving() {
+        let (mut store, handles) = numbered_store(4);
+        assert!(store.remove(handles[1]));
+        assert_eq!(store.index_of(handles[1]), None);
+        assert!(!store.remove(handles[1]));
+
+        // The freed slot is reused under a new generation
+        let reused = store.push(numbered(9));
+        assert_eq!(store.len(), 4);
+        assert_eq!(store.index_of(handles[1]), None);
+        assert_eq!(store.positions()[store.index_of(reused).unwrap()].x, 9.0);
+        assert_ne!(reused, handles[1]);
+
+        // The particle moved into the hole is still reachable through its handle
+        let index = store.index_of(handles[3]).unwrap();
+        assert_eq!(store.positions()[index].x, 3.0);
+    }
+
+    #[test]
+    fn handles_follow_particles_through_mixed_removals() {
+        let (mut store, handles) = numbered_store(10);
+        store.swap_remove(0);
+        store.remove(handles[7]);
+        store.remove_indices(&mut [2, 5, store.len() - 1]);
+        store.push(numbered(10));
+        assert_eq!(tags(&store), vec![1, 3, 4, 6, 9, 10]);
+        assert_consistent(&store);
+
+        for (i, &handle) in handles.iter().enumerate() {
+            match store.index_of(handle) {
+                Some(index) => assert_eq!(store.attributes()[index].user_data, i as u64),
+                None => assert!([0, 2, 5, 7, 8].contains(&i), "lost particle {}", i),
+            }
+        }
+    }
+
+    #[test]
+    fn remove_indices_removes_exactly_those_particles() {
+        let (mut store, _) = numbered_store(10);
+        // Unsorted, and including the last index, which a low-first pass would have moved
+        store.remove_indices(&mut [3, 9, 0, 4]);
+        assert_eq!(tags(&store), vec![1, 2, 5, 6, 7, 8]);
+        assert_consistent(&store);
+    }
+
+    #[test]
+    fn retain_checks_every_particle_once() {
+        let (mut store, _) = numbered_store(10);
+        let mut seen = Vec::new();
+        // Dropping a run at the end means each swapped-in particle must be checked too
+        store.retain(|store, i| {
+            let tag = store.attributes()[i].user_data;
+            seen.push(tag);
+            tag % 3 != 0 && tag < 8
+        });
+        seen.sort_unstable();
+        assert_eq!(seen, (0..10).collect::<Vec<_>>());
+        assert_eq!(tags(&store), vec![1, 2, 4, 5, 7]);
+        assert_consistent(&store);
+
+        store.clear();
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn remove_oldest_follows_push_order() {
+        let (mut store, handles) = numbered_store(10);
+        store.remove(handles[1]);
+        store.swap_remove(store.index_of(handles[4]).unwrap());
+        let mut reported = Vec::new();
+        store.remove_oldest(3, |store, i| reported.push(store.attributes()[i].user_data));
+        // Stale entries for 1 and 4 are skipped
+        assert_eq!(reported, vec![0, 2, 3]);
+        assert_eq!(tags(&store), vec![5, 6, 7, 8, 9]);
+        assert_consistent(&store);
+
+        // A reused slot counts as the newest particle
+        store.push(numbered(10));
+        store.remove_oldest(5, |_, _| {});
+        assert_eq!(tags(&store), vec![10]);
+        store.remove_oldest(5, |_, _| {});
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn push_order_stays_in_proportion_to_the_store() {
+        let mut store = ParticleStore::new();
+        for i in 0..20 * PUSH_ORDER_SLACK as u64 {
+            store.push(numbered(i));
+            store.retain(|store, index| store.attributes()[index].user_data % 10 == 0);
+        }
+        assert!(store.push_order.len() <= 2 * store.len() + PUSH_ORDER_SLACK + 1);
+        let oldest = store.attributes().iter().map(|a| a.user_data).min().unwrap();
+        let mut reported = Vec::new();
+        store.remove_oldest(1, |store, i| reported.push(store.attributes()[i].user_data));
+        assert_eq!(reported, vec![oldest]);
+    }
+}
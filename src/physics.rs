@@ -1,165 +1,1456 @@
-use crate::particle::Particle;
+use crate::attributes::{AttributeRule, ParticleKind, ParticleState};
+use crate::body_collider::BodyColliders;
+use crate::boundary::{self, AxisBoundary, BoundaryMode, Escape, ResetAnimation};
+use crate::clock::{MonotonicClock, SharedClock};
+use crate::collider::{Collider, ColliderId, ColliderSlot};
+use crate::electrostatics::{self, ElectrostaticsConfig, PointCharge};
+use crate::emitter::{Emitter, EmitterId};
+use crate::events::{PhysicsEvent, RemovalCause};
+use crate::cloth::{Cloth, ClothConfig, SpringBreak};
+use crate::detachment::{DetachConfig, WallState};
+use crate::diagnostics::{FrameCounts, PhysicsStats, StatsCsv};
+use crate::force_field::{self, FieldId, FieldSlot, ForceField, JointAttachment};
+use crate::input::BodyJoints;
+use crate::integrator::{Integrator, IntegratorKind, IntegratorScratch};
+use crate::material::{MaterialId, MaterialTable};
+use crate::parallel::Execution;
+use crate::particle::{Particle, GRAVITY_STIFFNESS, MODULE_STIFFNESS};
+use crate::particle_store::ParticleStore;
+use crate::sleep::{SleepConfig, SleepTracker};
+use crate::spatial_hash::SpatialHash;
 use crate::vector::Vec3;
+use crate::wall_grid::WallGrid;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
+use std::path::Path;
+use std::sync::Arc;
+
+/// How wall particles pull on each other
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WallModel {
+    /// Each particle is pulled towards the mean displacement of a `d`-cell window
+    ModuleGravity,
+    /// Structural, shear and bend springs between grid neighbors
+    Cloth,
+}
+
+/// What happens when emitters want more force particles than the budget allows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Spawn what fits and drop the rest
+    DropNew,
+    /// Spawn everything and remove the oldest force particles to make room
+    RecycleOldest,
+    /// Scale every emitter's output down evenly so the total fits
+    ScaleRate,
+}
+
+/// Tunable parameters for the simulation loop
+#[derive(Debug, Clone)]
+pub struct PhysicsConfig {
+    /// Length of one fixed simulation step in seconds
+    pub fixed_dt: f64,
+    /// Number of sub-steps each fixed step is split into
+    pub substeps: u32,
+    /// Maximum fixed steps run by a single `update`, so a long stall cannot spiral
+    pub max_steps_per_update: u32,
+    /// Test every force particle against every wall particle instead of using the
+    /// spatial hash. Slow; kept to validate the broadphase.
+    pub brute_force_collisions: bool,
+    /// Layout of the wall particles
+    pub wall_grid: WallGrid,
+    /// Time-stepping scheme for the wall
+    pub integrator: IntegratorKind,
+    /// Spring pulling wall particles back to rest, in 1/s²
+    pub gravity_stiffness: f64,
+    /// Real gravity, in units/s². Acts on force particles and on wall particles that have
+    /// broken loose; attached wall particles are held in place by the wall model.
+    pub gravity: Vec3,
+    /// When wall particles break loose and when they respawn
+    pub detach: DetachConfig,
+    /// When resting wall particles stop being simulated
+    pub sleep: SleepConfig,
+    /// Coulomb forces between particles carrying a `charge` attribute
+    pub electrostatics: ElectrostaticsConfig,
+    /// How wall particles pull on each other
+    pub wall_model: WallModel,
+    /// Pull of the surrounding module on each wall particle, in 1/s²
+    pub module_stiffness: f64,
+    /// Springs used when `wall_model` is `Cloth`
+    pub cloth: ClothConfig,
+    /// Remove force particles once they hit the wall instead of letting them bounce off
+    pub consume_force_particles: bool,
+    /// Maximum number of live force particles. The default is the 100k the particle store
+    /// is sized to sustain, short of the 150k the default hand emitter can produce.
+    pub particle_budget: usize,
+    pub overflow_policy: OverflowPolicy,
+    /// Seed for the particle RNG; `None` picks one at random. The seed in use is logged
+    /// and available from `PhysicsSystem::seed`.
+    pub seed: Option<u64>,
+    /// What happens to wall particles that cross in front of the wall plane; the plane
+    /// follows `wall_grid`. By default they glide back to rest; `None` leaves it open.
+    /// Detached particles are free to cross it until they respawn.
+    pub wall_plane: Option<BoundaryMode>,
+    /// Further limits on where wall particles may go
+    pub wall_boundaries: Vec<AxisBoundary>,
+    /// Limits on where force particles may go
+    pub force_boundaries: Vec<AxisBoundary>,
+    /// Run the per-particle loops on one thread or across the rayon pool; results are identical
+    pub execution: Execution,
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        Self {
+            fixed_dt: 1.0 / 60.0,
+            substeps: 1,
+            max_steps_per_update: 8,
+            brute_force_collisions: false,
+            wall_grid: WallGrid::default(),
+            integrator: IntegratorKind::SemiImplicitEuler,
+            gravity_stiffness: GRAVITY_STIFFNESS,
+            gravity: Vec3::new(),
+            detach: DetachConfig::default(),
+            sleep: SleepConfig::default(),
+            electrostatics: ElectrostaticsConfig::default(),
+            wall_model: WallModel::ModuleGravity,
+            module_stiffness: MODULE_STIFFNESS,
+            cloth: ClothConfig::default(),
+            consume_force_particles: true,
+            particle_budget: 100_000,
+            overflow_policy: OverflowPolicy::RecycleOldest,
+            seed: None,
+            wall_plane: Some(BoundaryMode::Reset { duration: 0.5 }),
+            wall_boundaries: Vec::new(),
+            force_boundaries: Vec::new(),
+            execution: Execution::SingleThreaded,
+        }
+    }
+}
 
 pub struct PhysicsSystem {
+    pub config: PhysicsConfig,
+    clock: SharedClock,
     pub wall_particles: Vec<Particle>,
-    pub force_particles: Vec<Particle>,
-    pub sphere_width_number: i32,
-    pub sphere_height_number: i32,
+    pub force_particles: ParticleStore,
+    wall_grid: WallGrid,
+    cloth: Cloth,
+    spring_breaks: Vec<SpringBreak>,
+    events: Vec<PhysicsEvent>,
+    /// Attached, detached or returning, by wall index
+    wall_states: Vec<WallState>,
+    sleep: SleepTracker,
     pub d: i32,
-    pub m1: f64, // mass of wall particles
-    pub m2: f64, // mass of force particles
+    pub materials: MaterialTable,
     pub ball_radius: f64,
-    pub force_radius: f64,
-    pub force_power_rate: f64,
-    pub make_force_num: usize,
+    accumulator: f64,
+    /// Seconds simulated so far: the sum of every step taken. Spawn times, lifetimes and
+    /// ages are measured on this, never on the clock, so results do not depend on frame timing.
+    sim_time: f64,
+    seed: u64,
+    rng: ChaCha8Rng,
+    integrator: Box<dyn Integrator>,
+    wall_scratch: IntegratorScratch,
+    /// One per chunk of force particles, so parallel chunks never share buffers
+    force_scratch: Vec<IntegratorScratch>,
+    wall_hash: SpatialHash,
+    charges: Vec<PointCharge>,
+    charge_hash: SpatialHash,
+    wall_positions: Vec<Vec3>,
+    wall_velocities: Vec<Vec3>,
+    fields: Vec<FieldSlot>,
+    colliders: Vec<ColliderSlot>,
+    body_colliders: Option<BodyColliders>,
+    attribute_rules: Vec<Box<dyn AttributeRule>>,
+    /// Impulse each wall particle took from force particles during the current step
+    wall_impulses: Vec<f64>,
+    emitters: Vec<Emitter>,
+    emitter_due: Vec<usize>,
+    dropped_spawns: u64,
+    recycled_particles: u64,
+    frame_counts: FrameCounts,
+    stats_csv: Option<StatsCsv>,
 }
 
 impl PhysicsSystem {
     pub fn new() -> Self {
-        let sphere_width_number = 21;
-        let sphere_height_number = 21;
+        Self::with_config(PhysicsConfig::default())
+    }
+
+    pub fn with_config(config: PhysicsConfig) -> Self {
+        Self::with_clock(config, Arc::new(MonotonicClock::new()))
+    }
+
+    pub fn with_clock(config: PhysicsConfig, clock: SharedClock) -> Self {
         let d = 10;
         let ball_radius = 5.0;
-        let force_radius = 1.0;
 
-        let mut wall_particles = Vec::new();
+        let wall_mass = 1.0;
 
-        // Create wall of particles
-        for y in -20..0 {
-            for x in -20..20 {
-                let particle = Particle::new(
-                    (x as f64) * ball_radius * 2.0,
-                    (y as f64) * ball_radius * 2.0,
-                    0.0,
-                );
-                let mut p = particle;
-                p.set_radius(ball_radius);
-                wall_particles.push(p);
+        let wall_grid = config.wall_grid.clone();
+        let mut wall_particles = wall_grid.build_particles(ball_radius);
+        for particle in &mut wall_particles {
+            particle.set_mass(wall_mass);
+        }
+
+        let cloth = Cloth::from_grid(&wall_grid, &wall_particles, &config.cloth);
+        if config.wall_model == WallModel::Cloth && config.cloth.pin_edges {
+            for (index, particle) in wall_particles.iter_mut().enumerate() {
+                let (column, row) = wall_grid.coords(index);
+                if column == 0 || row == 0 || column + 1 == wall_grid.columns || row + 1 == wall_grid.rows {
+                    particle.pin();
+                }
             }
         }
 
+        let mut wall_hash = SpatialHash::new();
+        wall_hash.rebuild(&wall_particles);
+        let wall_states = vec![WallState::Attached; wall_particles.len()];
+        let sleep = SleepTracker::new(wall_particles.len());
+        let wall_impulses = vec![0.0; wall_particles.len()];
+
+        let integrator = config.integrator.create();
+
+        let seed = config.seed.unwrap_or_else(rand::random);
+        log::info!("Physics RNG seed: {}", seed);
+
         Self {
+            config,
+            clock,
             wall_particles,
-            force_particles: Vec::new(),
-            sphere_width_number,
-            sphere_height_number,
+            force_particles: ParticleStore::new(),
+            wall_grid,
+            cloth,
+            spring_breaks: Vec::new(),
+            events: Vec::new(),
+            wall_states,
+            sleep,
             d,
-            m1: 1.0,
-            m2: 1.0,
+            materials: MaterialTable::new(),
             ball_radius,
-            force_radius,
-            force_power_rate: 0.4,
-            make_force_num: 500,
+            accumulator: 0.0,
+            sim_time: 0.0,
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            integrator,
+            wall_scratch: IntegratorScratch::new(),
+            force_scratch: Vec::new(),
+            wall_hash,
+            charges: Vec::new(),
+            charge_hash: SpatialHash::new(),
+            wall_positions: Vec::new(),
+            wall_velocities: Vec::new(),
+            fields: Vec::new(),
+            colliders: Vec::new(),
+            body_colliders: None,
+            attribute_rules: Vec::new(),
+            wall_impulses,
+            emitters: Vec::new(),
+            emitter_due: Vec::new(),
+            dropped_spawns: 0,
+            recycled_particles: 0,
+            frame_counts: FrameCounts::default(),
+            stats_csv: None,
         }
     }
 
-    pub fn update(&mut self) {
-        self.update_wall_module_forces();
-        self.update_wall_physics();
-        self.update_force_physics();
-        self.handle_collisions();
+    /// Advance the simulation by `dt` seconds of real time.
+    /// Time is consumed in fixed steps of `config.fixed_dt`; any remainder carries over to the next call.
+    pub fn update(&mut self, dt: f64) {
+        self.frame_counts = FrameCounts::default();
+        self.events.clear();
+        self.spring_breaks.clear();
+        self.accumulator += dt.max(0.0);
+
+        let mut steps = 0;
+        while self.accumulator >= self.config.fixed_dt {
+            if steps >= self.config.max_steps_per_update {
+                // Drop the backlog rather than trying to catch up forever
+                self.accumulator = 0.0;
+                break;
+            }
+            self.step(self.config.fixed_dt);
+            self.accumulator -= self.config.fixed_dt;
+            steps += 1;
+        }
+
+        if self.stats_csv.is_some() {
+            let stats = self.stats();
+            if let Err(err) = self.stats_csv.as_mut().map_or(Ok(()), |csv| csv.write(&stats)) {
+                log::warn!("Stopped streaming physics stats: {}", err);
+                self.stats_csv = None;
+            }
+        }
+    }
+
+    /// Run exactly one fixed step of `dt` seconds, split into `config.substeps` sub-steps
+    pub fn step(&mut self, dt: f64) {
+        self.frame_counts.steps += 1;
+        for slot in &mut self.fields {
+            slot.field.advance(dt);
+        }
+        self.run_emitters(dt);
+
+        let substeps = self.config.substeps.max(1);
+        let h = dt / substeps as f64;
+        for _ in 0..substeps {
+            self.apply_electrostatics(h);
+            self.update_wall_physics(h);
+            self.update_force_physics(h);
+            self.handle_collisions();
+            self.handle_loose_collisions();
+            self.handle_collider_contacts();
+            self.apply_boundaries(h);
+        }
+        self.sim_time += dt;
+        self.update_attributes(dt);
         self.remove_expired_forces();
     }
 
-    fn update_wall_module_forces(&mut self) {
-        let wall_count = self.wall_particles.len();
-        let width = 40; // -20 to 20
-        
-        // Calculate module forces for wall particles
-        for y in self.d..(20 - self.d) {
-            for x in self.d..(40 - self.d) {
-                let center_idx = (y + 20) * width + (x - 20 + 20);
-                if center_idx < 0 || center_idx >= wall_count as i32 {
-                    continue;
-                }
+    /// Seconds of simulated time so far
+    pub fn sim_time(&self) -> f64 {
+        self.sim_time
+    }
+
+    fn sim_time_ms(&self) -> u64 {
+        (self.sim_time * 1000.0).round() as u64
+    }
+
+    /// Fraction of a fixed step left in the accumulator, for interpolating rendered positions
+    pub fn interpolation_alpha(&self) -> f64 {
+        self.accumulator / self.config.fixed_dt
+    }
+
+    fn update_wall_physics(&mut self, dt: f64) {
+        if self.config.sleep.enabled {
+            self.wake_wall_particles(dt);
+            if self.sleep.all_asleep() {
+                return;
+            }
+        } else {
+            self.wake_all_wall_particles();
+        }
+
+        self.wall_positions.clear();
+        self.wall_velocities.clear();
+        for particle in &self.wall_particles {
+            self.wall_positions.push(particle.position);
+            self.wall_velocities.push(particle.velocity);
+        }
+
+        let forces = WallForces {
+            particles: &self.wall_particles,
+            grid: &self.wall_grid,
+            d: self.d.max(0) as usize,
+            config: &self.config,
+            cloth: &self.cloth,
+            fields: &self.fields,
+            states: &self.wall_states,
+            asleep: self.sleep.asleep(),
+            execution: self.config.execution,
+        };
+        let mut acceleration = |positions: &[Vec3], velocities: &[Vec3], out: &mut [Vec3]| {
+            forces.evaluate(positions, velocities, out);
+        };
+        self.integrator.integrate(
+            &mut self.wall_positions,
+            &mut self.wall_velocities,
+            dt,
+            &mut acceleration,
+            &mut self.wall_scratch,
+        );
+
+        for (i, particle) in self.wall_particles.iter_mut().enumerate() {
+            particle.position = self.wall_positions[i];
+            particle.velocity = self.wall_velocities[i];
+        }
+
+        if self.config.wall_model == WallModel::Cloth {
+            self.cloth.break_overstretched(&self.wall_particles, &mut self.spring_breaks);
+        }
+
+        if self.config.sleep.enabled {
+            self.settle_wall_particles();
+        }
+    }
+
+    /// Whether an attached wall particle is close enough to rest to count towards sleep
+    fn is_resting(&self, index: usize) -> bool {
+        let particle = &self.wall_particles[index];
+        let config = &self.config.sleep;
+        self.wall_states[index].is_attached()
+            && particle.displacement().magnitude() < config.displacement
+            && particle.velocity.magnitude() < config.velocity
+    }
 
-                let mut module_force = Vec3::new();
-                
-                for j in -self.d..=self.d {
-                    for i in -self.d..=self.d {
-                        let neighbor_idx = ((y + j + 20) * width + (x + i - 20 + 20)) as usize;
-                        if neighbor_idx < wall_count {
-                            let neighbor_pos = self.wall_particles[neighbor_idx].position;
-                            module_force.add(&neighbor_pos);
+    /// Wake sleeping wall particles that something has disturbed: a collision or boundary
+    /// moved them, a field pulls on them, or a coupled neighbor is moving
+    fn wake_wall_particles(&mut self, dt: f64) {
+        if self.sleep.sleeping_count() == 0 {
+            return;
+        }
+
+        for index in 0..self.wall_particles.len() {
+            if !self.sleep.is_asleep(index) {
+                continue;
+            }
+            let particle = &self.wall_particles[index];
+            let disturbed = !self.is_resting(index) || {
+                let field = force_field::total_acceleration(&self.fields, ParticleKind::Wall, &particle.position, &particle.velocity);
+                field.magnitude() * dt >= self.config.sleep.velocity
+            };
+            if disturbed && self.sleep.wake(index) {
+                self.events.push(PhysicsEvent::Woke { wall_index: index });
+            }
+        }
+
+        // Motion spreads to sleeping neighbors through the module pull or the cloth springs.
+        // Loose particles are out of the module and have lost their springs, so they pull on
+        // nobody; likewise a cloth particle with every spring torn has no neighbors to wake.
+        for index in 0..self.wall_particles.len() {
+            if self.sleep.is_asleep(index)
+                || self.wall_particles[index].is_pinned()
+                || self.wall_states[index].is_loose()
+                || self.is_resting(index)
+            {
+                continue;
+            }
+            match self.config.wall_model {
+                WallModel::ModuleGravity => {
+                    for neighbor_idx in self.wall_grid.neighbors_within(index, self.d.max(0) as usize) {
+                        if self.sleep.wake(neighbor_idx) {
+                            self.events.push(PhysicsEvent::Woke { wall_index: neighbor_idx });
+                        }
+                    }
+                }
+                WallModel::Cloth => {
+                    for neighbor_idx in self.cloth.neighbors(index) {
+                        if self.sleep.wake(neighbor_idx) {
+                            self.events.push(PhysicsEvent::Woke { wall_index: neighbor_idx });
                         }
                     }
                 }
+            }
+        }
+    }
 
-                if (center_idx as usize) < wall_count {
-                    self.wall_particles[center_idx as usize].set_around_module(&module_force);
+    /// Put wall particles that have rested for `config.sleep.steps` sub-steps to sleep
+    fn settle_wall_particles(&mut self) {
+        for index in 0..self.wall_particles.len() {
+            if self.sleep.is_asleep(index) {
+                continue;
+            }
+            let resting = self.is_resting(index);
+            if self.sleep.observe(index, resting, &self.config.sleep) {
+                self.wall_particles[index].velocity = Vec3::new();
+                self.events.push(PhysicsEvent::Slept { wall_index: index });
+            }
+        }
+    }
+
+    fn wake_all_wall_particles(&mut self) {
+        if self.sleep.sleeping_count() > 0 {
+            for index in 0..self.wall_particles.len() {
+                if self.sleep.is_asleep(index) {
+                    self.events.push(PhysicsEvent::Woke { wall_index: index });
                 }
             }
         }
+        self.sleep.wake_all();
     }
 
-    fn update_wall_physics(&mut self) {
-        for particle in &mut self.wall_particles {
-            particle.gravity();
-            particle.module_gravity(self.d);
-            particle.move_particle();
+    /// Kick every charged particle's velocity by its Coulomb acceleration over `dt`, evaluated
+    /// at the current positions. Sleeping wall particles wake once the kicks add up.
+    fn apply_electrostatics(&mut self, dt: f64) {
+        let config = &self.config.electrostatics;
+        if !config.enabled || config.coulomb == 0.0 {
+            return;
+        }
+
+        self.charges.clear();
+        for (index, particle) in self.wall_particles.iter().enumerate() {
+            if particle.attributes.charge != 0.0 {
+                self.charges.push(PointCharge {
+                    position: particle.position,
+                    charge: particle.attributes.charge,
+                    inv_mass: particle.inv_mass,
+                    wall: true,
+                    index,
+                });
+            }
+        }
+        let store = &self.force_particles;
+        for (index, attributes) in store.attributes().iter().enumerate() {
+            if attributes.charge != 0.0 {
+                self.charges.push(PointCharge {
+                    position: store.positions()[index],
+                    charge: attributes.charge,
+                    inv_mass: store.inv_masses()[index],
+                    wall: false,
+                    index,
+                });
+            }
+        }
+        if self.charges.len() < 2 {
+            return;
+        }
+
+        self.charge_hash
+            .rebuild_points(self.charges.iter().map(|charge| charge.position), config.cutoff);
+        let (charges, hash) = (&self.charges, &self.charge_hash);
+        let accelerations = self.config.execution.map_with_scratch(charges.len(), Vec::new, |candidates, index| {
+            electrostatics::acceleration(config, charges, hash, index, candidates)
+        });
+
+        let velocities = self.force_particles.columns_mut().velocity;
+        for (charge, acceleration) in self.charges.iter().zip(accelerations) {
+            let kick = acceleration * dt;
+            if !charge.wall {
+                velocities[charge.index].add(&kick);
+                continue;
+            }
+            // Like a field, a pull too weak to wake a sleeping particle leaves it be
+            if self.sleep.is_asleep(charge.index) {
+                if kick.magnitude() < self.config.sleep.velocity {
+                    continue;
+                }
+                self.sleep.wake(charge.index);
+                self.events.push(PhysicsEvent::Woke { wall_index: charge.index });
+            }
+            self.wall_particles[charge.index].velocity.add(&kick);
         }
     }
 
-    fn update_force_physics(&mut self) {
-        for particle in &mut self.force_particles {
-            particle.move_particle();
+    fn update_force_physics(&mut self, dt: f64) {
+        let columns = self.force_particles.columns_mut();
+        let integrator = self.integrator.as_ref();
+        let fields = &self.fields;
+        let gravity = self.config.gravity;
+        let chunks = columns.position.len().div_ceil(FORCE_CHUNK).max(1);
+        if self.force_scratch.len() < chunks {
+            self.force_scratch.resize_with(chunks, IntegratorScratch::new);
+        }
+        match self.config.execution {
+            Execution::SingleThreaded => integrate_forces(
+                integrator,
+                fields,
+                gravity,
+                columns.position,
+                columns.velocity,
+                columns.damping,
+                dt,
+                &mut self.force_scratch[0],
+            ),
+            // Force particles do not interact, so each chunk can be integrated on its own
+            Execution::Parallel => columns
+                .position
+                .par_chunks_mut(FORCE_CHUNK)
+                .zip(columns.velocity.par_chunks_mut(FORCE_CHUNK))
+                .zip(columns.damping.par_chunks(FORCE_CHUNK))
+                .zip(self.force_scratch.par_iter_mut())
+                .for_each(|(((positions, velocities), damping), scratch)| {
+                    integrate_forces(integrator, fields, gravity, positions, velocities, damping, dt, scratch)
+                }),
         }
     }
 
+    /// Detect every force particle's collision in parallel, then resolve the hits on each wall
+    /// particle in force-particle order. Wall particles never share a force particle, so the
+    /// groups are independent and the outcome does not depend on scheduling.
     fn handle_collisions(&mut self) {
+        // Rebuilt even in brute-force mode so `particles_near` stays current
+        self.wall_hash.rebuild(&self.wall_particles);
+
+        let execution = self.config.execution;
+        let this = &*self;
+        let hits = execution.map_with_scratch(this.force_particles.len(), Vec::new, |candidates, force_idx| {
+            if this.config.brute_force_collisions {
+                this.first_collision_brute_force(force_idx)
+            } else {
+                this.first_collision_broadphase(force_idx, candidates)
+            }
+        });
+
+        let mut hits: Vec<(usize, usize)> = hits
+            .into_iter()
+            .enumerate()
+            .filter_map(|(force_idx, hit)| hit.map(|wall_idx| (wall_idx, force_idx)))
+            .collect();
+        if hits.is_empty() {
+            return;
+        }
+        // Stable, so each wall particle's hits stay in force-particle order
+        hits.sort_by_key(|&(wall_idx, _)| wall_idx);
+        let groups: Vec<&[(usize, usize)]> = hits.chunk_by(|a, b| a.0 == b.0).collect();
+
+        let outcomes = execution.map(groups.len(), |group| this.resolve_wall_hits(groups[group]));
+
+        self.frame_counts.collisions += hits.len() as u64;
         let mut forces_to_remove = Vec::new();
+        for outcome in outcomes {
+            self.wall_particles[outcome.wall_idx] = outcome.wall_particle;
+            for hit in &outcome.forces {
+                self.force_particles.set_kinematics(hit.force_idx, &hit.force_particle);
+                if self.config.consume_force_particles {
+                    forces_to_remove.push(hit.force_idx);
+                }
+                self.wall_impulses[outcome.wall_idx] += hit.impulse;
+                self.events.push(PhysicsEvent::Collided {
+                    wall_index: outcome.wall_idx,
+                    impulse: hit.impulse,
+                    point: hit.point,
+                });
+            }
+            if self.config.wall_model == WallModel::Cloth && outcome.max_impulse > self.config.cloth.tear_impulse {
+                self.cloth.tear_at(outcome.wall_idx, &mut self.spring_breaks);
+            }
+            let wall_particle = &self.wall_particles[outcome.wall_idx];
+            let impact_energy = 0.5 * outcome.max_impulse * outcome.max_impulse * wall_particle.inv_mass;
+            if impact_energy > self.config.detach.impact_energy {
+                self.detach_wall_particle(outcome.wall_idx);
+            }
+        }
+
+        self.push_removals(&forces_to_remove, RemovalCause::Consumed);
+        self.force_particles.remove_indices(&mut forces_to_remove);
+    }
+
+    /// Report the force particles at `indices` as removed, before they are
+    fn push_removals(&mut self, indices: &[usize], cause: RemovalCause) {
+        for &index in indices {
+            self.events.push(PhysicsEvent::Removed {
+                handle: self.force_particles.handle(index),
+                position: self.force_particles.positions()[index],
+                cause,
+            });
+        }
+    }
+
+    /// Apply every hit on one wall particle in turn, on copies of the particles involved
+    fn resolve_wall_hits(&self, hits: &[(usize, usize)]) -> WallHits {
+        let wall_idx = hits[0].0;
+        let mut wall_particle = self.wall_particles[wall_idx].clone();
+        let wall_material = self.materials.get(wall_particle.material);
+        let mut forces = Vec::with_capacity(hits.len());
+        let mut max_impulse: f64 = 0.0;
+
+        for &(_, force_idx) in hits {
+            let mut force_particle = self.force_particles.get(force_idx);
+            let (restitution, friction) = wall_material.combine(&self.materials.get(force_particle.material));
+            // Where the surfaces meet along the line between the centers
+            let offset = force_particle.position - wall_particle.position;
+            let point = wall_particle.position
+                + offset * (wall_particle.radius / (wall_particle.radius + force_particle.radius));
+            let impulse = wall_particle.resolve_collision(&mut force_particle, restitution, friction);
+            max_impulse = max_impulse.max(impulse);
+            forces.push(ForceHit {
+                force_idx,
+                force_particle,
+                impulse,
+                point,
+            });
+        }
+
+        WallHits {
+            wall_idx,
+            wall_particle,
+            forces,
+            max_impulse,
+        }
+    }
+
+    /// Lowest-indexed wall particle hit by a force particle, testing the whole wall
+    fn first_collision_brute_force(&self, force_idx: usize) -> Option<usize> {
+        let position = &self.force_particles.positions()[force_idx];
+        let radius = self.force_particles.radii()[force_idx];
+        self.wall_particles
+            .iter()
+            .position(|wall_particle| wall_particle.overlaps_sphere(position, radius))
+    }
+
+    /// Lowest-indexed wall particle hit by a force particle, testing only nearby cells.
+    /// Picking the lowest index keeps results identical to the brute-force path.
+    fn first_collision_broadphase(&self, force_idx: usize, candidates: &mut Vec<usize>) -> Option<usize> {
+        let position = &self.force_particles.positions()[force_idx];
+        let radius = self.force_particles.radii()[force_idx];
+        candidates.clear();
+        self.wall_hash.query(position, radius, candidates);
+        candidates
+            .iter()
+            .copied()
+            .filter(|&wall_idx| self.wall_particles[wall_idx].overlaps_sphere(position, radius))
+            .min()
+    }
+
+    /// Push wall and force particles out of every enabled collider and the body
+    fn handle_collider_contacts(&mut self) {
+        let mut wall_colliders: Vec<(&dyn Collider, MaterialId)> = self
+            .colliders
+            .iter()
+            .filter(|slot| slot.enabled)
+            .map(|slot| (slot.collider.as_ref(), slot.material))
+            .collect();
+        let mut force_colliders = wall_colliders.clone();
+        if let Some(body) = self.body_colliders.as_ref().filter(|body| body.enabled) {
+            let capsules = body.capsules().iter().map(|capsule| (capsule as &dyn Collider, body.material));
+            wall_colliders.extend(capsules.clone());
+            if body.collide_force_particles {
+                force_colliders.extend(capsules);
+            }
+        }
+
+        let execution = self.config.execution;
+        let materials = &self.materials;
+
+        if !wall_colliders.is_empty() {
+            execution.for_each_mut(&mut self.wall_particles, |_, particle| {
+                resolve_collider_contacts(&wall_colliders, materials, particle);
+            });
+        }
+
+        if !force_colliders.is_empty() {
+            let columns = self.force_particles.columns_mut();
+            let (radius, inv_mass, material) = (&*columns.radius, &*columns.inv_mass, &*columns.material);
+            execution.for_each_pair_mut(columns.position, columns.velocity, |i, position, velocity| {
+                let mut particle = Particle::new(position.x, position.y, position.z);
+                particle.velocity = *velocity;
+                particle.radius = radius[i];
+                particle.inv_mass = inv_mass[i];
+                particle.material = material[i];
+                if resolve_collider_contacts(&force_colliders, materials, &mut particle) {
+                    *position = particle.position;
+                    *velocity = particle.velocity;
+                }
+            });
+        }
+    }
+
+    /// Collide loose wall particles with each other and with the wall, so they pile up
+    fn handle_loose_collisions(&mut self) {
+        let loose: Vec<usize> = (0..self.wall_particles.len())
+            .filter(|&index| self.wall_states[index].is_detached())
+            .collect();
+        if loose.is_empty() {
+            return;
+        }
+
+        let mut candidates = Vec::new();
+        for &index in &loose {
+            let particle = &self.wall_particles[index];
+            candidates.clear();
+            self.wall_hash.query(&particle.position, particle.radius, &mut candidates);
+            candidates.sort_unstable();
+
+            for &other in &candidates {
+                // Each loose pair once, from its lower index
+                if other == index || (other < index && self.wall_states[other].is_detached()) {
+                    continue;
+                }
+                let (a, b) = pair_mut(&mut self.wall_particles, index, other);
+                if !a.collides_with(b) {
+                    continue;
+                }
+                let (restitution, friction) = self.materials.get(a.material).combine(&self.materials.get(b.material));
+                a.resolve_collision(b, restitution, friction);
+                self.frame_counts.collisions += 1;
+            }
+        }
+    }
+
+    /// Break a wall particle out of the wall; it falls under gravity until it respawns
+    fn detach_wall_particle(&mut self, index: usize) {
+        if !self.wall_states[index].is_attached() || self.wall_particles[index].is_pinned() {
+            return;
+        }
+        self.wall_states[index] = WallState::Detached {
+            remaining: self.config.detach.respawn_after,
+        };
+        self.cloth.detach_at(index, &mut self.spring_breaks);
+        self.events.push(PhysicsEvent::Detached { wall_index: index });
+    }
+
+    /// Put a wall particle back in the wall, reconnecting it to its attached neighbors
+    fn reattach_wall_particle(&mut self, index: usize) {
+        self.wall_states[index] = WallState::Attached;
+        self.events.push(PhysicsEvent::Reattached { wall_index: index });
+        let states = &self.wall_states;
+        self.cloth.restore_at(index, &self.wall_grid, &self.wall_particles, &self.config.cloth, |other| {
+            !states[other].is_loose()
+        });
+    }
+
+    /// Enforce the wall and force boundaries, detach overstretched wall particles and move
+    /// detached and returning ones `dt` seconds along
+    fn apply_boundaries(&mut self, dt: f64) {
+        let execution = self.config.execution;
+        let wall_plane = self
+            .config
+            .wall_plane
+            .map(|mode| self.wall_grid.front_boundary(WALL_PLANE_SLACK, mode));
+
+        for index in 0..self.wall_particles.len() {
+            let particle = &self.wall_particles[index];
+            if particle.is_pinned() {
+                continue;
+            }
+            if self.wall_states[index].is_attached() && particle.displacement().magnitude() > self.config.detach.displacement {
+                self.detach_wall_particle(index);
+            }
+
+            let particle = &mut self.wall_particles[index];
+            let reattach = match self.wall_states[index] {
+                WallState::Attached => false,
+                WallState::Detached { ref mut remaining } => {
+                    *remaining -= dt;
+                    if *remaining <= 0.0 {
+                        self.wall_states[index] = WallState::Returning {
+                            animation: ResetAnimation::new(particle.position, self.config.detach.respawn_duration),
+                            reattach: true,
+                        };
+                    }
+                    true
+                }
+                WallState::Returning { .. } => continue,
+            };
+
+            // Detached pieces fall and pile up in front of the wall until they respawn,
+            // so only the further limits hold them
+            let escape = wall_plane
+                .filter(|_| !reattach)
+                .and_then(|plane| plane.apply(&mut particle.position, &mut particle.velocity))
+                .or_else(|| {
+                    boundary::apply_boundaries(&self.config.wall_boundaries, &mut particle.position, &mut particle.velocity)
+                });
+            match escape {
+                Some(Escape::Kill) => {
+                    particle.reset_to_original();
+                    self.wall_states[index] = WallState::Attached;
+                    if reattach {
+                        self.reattach_wall_particle(index);
+                    }
+                }
+                Some(Escape::Reset { duration }) => {
+                    self.wall_states[index] = WallState::Returning {
+                        animation: ResetAnimation::new(particle.position, duration),
+                        reattach,
+                    };
+                }
+                None => {}
+            }
+        }
 
-        for (force_idx, force_particle) in self.force_particles.iter().enumerate() {
-            for wall_particle in &mut self.wall_particles {
-                if force_particle.collides_with(wall_particle) {
-                    wall_particle.apply_collision(force_particle, self.m1, self.m2);
-                    forces_to_remove.push(force_idx);
-                    break;
+        for index in 0..self.wall_particles.len() {
+            if let WallState::Returning { mut animation, reattach } = self.wall_states[index] {
+                if animation.advance(dt, &mut self.wall_particles[index]) {
+                    self.wall_states[index] = WallState::Attached;
+                    if reattach {
+                        self.reattach_wall_particle(index);
+                    }
+                } else {
+                    self.wall_states[index] = WallState::Returning { animation, reattach };
                 }
             }
         }
 
-        // Remove collided forces (in reverse order to maintain indices)
-        for &idx in forces_to_remove.iter().rev() {
-            if idx < self.force_particles.len() {
-                self.force_particles.remove(idx);
+        let force_boundaries = &self.config.force_boundaries;
+        if force_boundaries.is_empty() {
+            return;
+        }
+        let columns = self.force_particles.columns_mut();
+        execution.for_each_pair_mut(columns.position, columns.velocity, |_, position, velocity| {
+            boundary::apply_boundaries(force_boundaries, position, velocity);
+        });
+        if force_boundaries.iter().any(AxisBoundary::removes) {
+            let events = &mut self.events;
+            self.force_particles.retain(|store, i| {
+                let position = store.positions()[i];
+                let escaped = force_boundaries
+                    .iter()
+                    .any(|boundary| boundary.removes() && boundary.is_outside(&position));
+                if escaped {
+                    events.push(PhysicsEvent::Removed {
+                        handle: store.handle(i),
+                        position,
+                        cause: RemovalCause::OutOfBounds,
+                    });
+                }
+                !escaped
+            });
+        }
+    }
+
+    /// Indices of wall particles whose spheres overlap the sphere at `point` with `radius`,
+    /// in ascending order. Uses wall positions as of the last completed step.
+    pub fn particles_near(&self, point: &Vec3, radius: f64) -> Vec<usize> {
+        let mut candidates = Vec::new();
+        self.wall_hash.query(point, radius, &mut candidates);
+        candidates.retain(|&wall_idx| self.wall_particles[wall_idx].overlaps_sphere(point, radius));
+        candidates.sort_unstable();
+        candidates
+    }
+
+    /// Age the force particles, then run the attribute rules over every particle
+    fn update_attributes(&mut self, dt: f64) {
+        let now_ms = self.sim_time_ms();
+        let execution = self.config.execution;
+        let force_rules: Vec<&dyn AttributeRule> = self
+            .attribute_rules
+            .iter()
+            .map(|rule| rule.as_ref())
+            .filter(|rule| rule.applies_to(ParticleKind::Force))
+            .collect();
+        let wall_rules: Vec<&dyn AttributeRule> = self
+            .attribute_rules
+            .iter()
+            .map(|rule| rule.as_ref())
+            .filter(|rule| rule.applies_to(ParticleKind::Wall))
+            .collect();
+
+        let columns = self.force_particles.columns_mut();
+        let (positions, velocities) = (&*columns.position, &*columns.velocity);
+        let (created_times, lifetimes_ms) = (&*columns.created_time, &*columns.lifetime_ms);
+        execution.for_each_mut(columns.attributes, |i, attributes| {
+            attributes.age = normalized_age(now_ms, created_times[i], lifetimes_ms[i]);
+            let state = ParticleState {
+                kind: ParticleKind::Force,
+                position: &positions[i],
+                velocity: &velocities[i],
+                impulse: 0.0,
+            };
+            for rule in &force_rules {
+                rule.update(attributes, &state, dt);
             }
+        });
+
+        if !wall_rules.is_empty() {
+            let impulses = &self.wall_impulses;
+            execution.for_each_mut(&mut self.wall_particles, |i, particle| {
+                let state = ParticleState {
+                    kind: ParticleKind::Wall,
+                    position: &particle.position,
+                    velocity: &particle.velocity,
+                    impulse: impulses[i],
+                };
+                for rule in &wall_rules {
+                    rule.update(&mut particle.attributes, &state, dt);
+                }
+            });
         }
+        self.wall_impulses.fill(0.0);
     }
 
     fn remove_expired_forces(&mut self) {
-        self.force_particles.retain(|particle| !particle.is_expired(5000)); // 5 second lifetime
+        let now_ms = self.sim_time_ms();
+        let before = self.force_particles.len();
+        let events = &mut self.events;
+        self.force_particles.retain(|store, i| {
+            let alive = now_ms.saturating_sub(store.created_times()[i]) <= store.lifetimes_ms()[i];
+            if !alive {
+                events.push(PhysicsEvent::Removed {
+                    handle: store.handle(i),
+                    position: store.positions()[i],
+                    cause: RemovalCause::Expired,
+                });
+            }
+            alive
+        });
+        self.frame_counts.expired += (before - self.force_particles.len()) as u64;
+    }
+
+    /// Seed the particle RNG was started from
+    pub fn seed(&self) -> u64 {
+        self.seed
     }
 
-    pub fn create_force_particles(&mut self, hand_position: &Vec3, hand_velocity: &Vec3, spread_distance: f64) {
-        if hand_velocity.magnitude() > 0.001 {
-            for _ in 0..self.make_force_num {
-                let spread_x = spread_distance * (rand::random::<f64>() - 0.5) * 0.05;
-                let spread_y = spread_distance * (rand::random::<f64>() - 0.5) * 0.05;
-                let spread_z = spread_distance * (rand::random::<f64>() - 0.5) * 0.05;
+    /// Restart the particle RNG from `seed`
+    pub fn reseed(&mut self, seed: u64) {
+        log::info!("Physics RNG reseeded: {}", seed);
+        self.seed = seed;
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+    }
 
-                let mut force_particle = Particle::new(
-                    hand_position.x + spread_x,
-                    hand_position.y + spread_y,
-                    hand_position.z + spread_z,
-                );
-                force_particle.set_radius(self.force_radius);
+    /// Register a force field acting on every particle
+    pub fn add_field(&mut self, field: Box<dyn ForceField>) -> FieldId {
+        self.fields.push(FieldSlot::new(field));
+        self.fields.len() - 1
+    }
 
-                let mut velocity = *hand_velocity;
-                velocity.mult(self.force_power_rate);
-                force_particle.add_velocity(&velocity);
+    /// Register a force field that follows a body joint
+    pub fn add_attached_field(&mut self, field: Box<dyn ForceField>, attachment: JointAttachment) -> FieldId {
+        let id = self.add_field(field);
+        // Stays off until `update_attachments` has placed it on the body
+        self.fields[id].attachment = Some(attachment);
+        self.fields[id].enabled = false;
+        id
+    }
 
-                self.force_particles.push(force_particle);
+    pub fn field_mut(&mut self, id: FieldId) -> Option<&mut FieldSlot> {
+        self.fields.get_mut(id)
+    }
+
+    pub fn fields(&self) -> &[FieldSlot] {
+        &self.fields
+    }
+
+    /// Register a solid shape that wall and force particles bounce off
+    pub fn add_collider(&mut self, collider: Box<dyn Collider>, material: MaterialId) -> ColliderId {
+        self.colliders.push(ColliderSlot::new(collider, material));
+        self.colliders.len() - 1
+    }
+
+    pub fn collider_mut(&mut self, id: ColliderId) -> Option<&mut ColliderSlot> {
+        self.colliders.get_mut(id)
+    }
+
+    pub fn colliders(&self) -> &[ColliderSlot] {
+        &self.colliders
+    }
+
+    /// Give the tracked body a physical presence; it follows the pose passed to `update_attachments`
+    pub fn set_body_colliders(&mut self, body_colliders: BodyColliders) {
+        self.body_colliders = Some(body_colliders);
+    }
+
+    pub fn body_colliders(&self) -> Option<&BodyColliders> {
+        self.body_colliders.as_ref()
+    }
+
+    pub fn body_colliders_mut(&mut self) -> Option<&mut BodyColliders> {
+        self.body_colliders.as_mut()
+    }
+
+    /// Move attached fields, emitters and body colliders to their joints and switch them on or
+    /// off for the current pose
+    pub fn update_attachments(&mut self, body: &BodyJoints) {
+        for slot in &mut self.fields {
+            if let Some(attachment) = slot.attachment {
+                slot.enabled = attachment.is_active(body);
+                slot.field.set_origin(body.position(attachment.joint) + attachment.offset);
             }
         }
+        for emitter in &mut self.emitters {
+            emitter.update_attachment(body);
+        }
+        if let Some(body_colliders) = &mut self.body_colliders {
+            body_colliders.update(body, self.clock.now());
+        }
+    }
+
+    /// Adjust wall particles by grid cell, e.g. to give regions their own mass or material
+    /// or to pin the border: `configure_wall(|column, row, particle| ...)`
+    pub fn configure_wall(&mut self, mut f: impl FnMut(usize, usize, &mut Particle)) {
+        for (index, particle) in self.wall_particles.iter_mut().enumerate() {
+            let (column, row) = self.wall_grid.coords(index);
+            f(column, row, particle);
+        }
     }
 
-    pub fn get_all_particles(&self) -> Vec<&Particle> {
-        let mut all_particles = Vec::new();
-        all_particles.extend(self.wall_particles.iter());
-        all_particles.extend(self.force_particles.iter());
-        all_particles
+    /// Replace the integrator, e.g. with a custom implementation
+    pub fn set_integrator(&mut self, integrator: Box<dyn Integrator>) {
+        self.integrator = integrator;
+    }
+
+    pub fn integrator(&self) -> &dyn Integrator {
+        self.integrator.as_ref()
+    }
+
+    pub fn wall_grid(&self) -> &WallGrid {
+        &self.wall_grid
+    }
+
+    pub fn cloth(&self) -> &Cloth {
+        &self.cloth
+    }
+
+    /// Attached, detached or returning, by wall index
+    pub fn wall_states(&self) -> &[WallState] {
+        &self.wall_states
+    }
+
+    /// Number of wall particles currently asleep
+    pub fn sleeping_count(&self) -> usize {
+        self.sleep.sleeping_count()
+    }
+
+    pub fn is_asleep(&self, wall_idx: usize) -> bool {
+        self.sleep.is_asleep(wall_idx)
+    }
+
+    /// Number of wall particles currently broken loose
+    pub fn detached_count(&self) -> usize {
+        self.wall_states.iter().filter(|state| state.is_detached()).count()
+    }
+
+    /// Take the springs severed since the last call.
+    /// Undrained breaks are dropped when the next `update` starts.
+    pub fn drain_spring_breaks(&mut self) -> Vec<SpringBreak> {
+        std::mem::take(&mut self.spring_breaks)
+    }
+
+    /// Take the events raised since the last call, oldest first.
+    /// Undrained events are dropped when the next `update` starts.
+    pub fn drain_events(&mut self) -> Vec<PhysicsEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Restore the wall: every particle back at rest and every spring reconnected
+    pub fn reset_wall(&mut self) {
+        for particle in &mut self.wall_particles {
+            particle.reset_to_original();
+        }
+        self.wall_states.fill(WallState::Attached);
+        self.wake_all_wall_particles();
+        self.cloth = Cloth::from_grid(&self.wall_grid, &self.wall_particles, &self.config.cloth);
+        self.wall_hash.rebuild(&self.wall_particles);
+    }
+
+    pub fn clock(&self) -> &SharedClock {
+        &self.clock
+    }
+
+    fn run_emitters(&mut self, dt: f64) {
+        let now_ms = self.sim_time_ms();
+        let budget = self.config.particle_budget;
+
+        self.emitter_due.clear();
+        for emitter in &mut self.emitters {
+            self.emitter_due.push(emitter.due(dt));
+        }
+        let total_due: usize = self.emitter_due.iter().sum();
+        if total_due == 0 {
+            return;
+        }
+
+        let available = budget.saturating_sub(self.force_particles.len());
+        let mut allowance = match self.config.overflow_policy {
+            OverflowPolicy::DropNew | OverflowPolicy::ScaleRate => available,
+            // Room is made afterwards; anything beyond the whole budget can never fit
+            OverflowPolicy::RecycleOldest => budget,
+        };
+        let scale = if self.config.overflow_policy == OverflowPolicy::ScaleRate && total_due > available {
+            available as f64 / total_due as f64
+        } else {
+            1.0
+        };
+
+        let mut spawned = 0;
+        for (emitter_id, (emitter, &due)) in self.emitters.iter().zip(&self.emitter_due).enumerate() {
+            let count = ((due as f64 * scale).floor() as usize).min(allowance);
+            for _ in 0..count {
+                let mut particle = emitter.spawn(now_ms, &mut self.rng);
+                particle.attributes.emitter = Some(emitter_id);
+                let position = particle.position;
+                let handle = self.force_particles.push(particle);
+                self.events.push(PhysicsEvent::Spawned {
+                    handle,
+                    emitter: emitter_id,
+                    position,
+                });
+            }
+            allowance -= count;
+            spawned += count;
+        }
+        self.dropped_spawns += (total_due - spawned) as u64;
+        self.frame_counts.spawned += spawned as u64;
+        self.frame_counts.dropped_spawns += (total_due - spawned) as u64;
+
+        let excess = self.force_particles.len().saturating_sub(budget);
+        if excess > 0 {
+            self.remove_oldest_forces(excess);
+            self.recycled_particles += excess as u64;
+            self.frame_counts.recycled += excess as u64;
+        }
+    }
+
+    /// Remove the `count` force particles added to the store earliest
+    fn remove_oldest_forces(&mut self, count: usize) {
+        let events = &mut self.events;
+        self.force_particles.remove_oldest(count, |store, i| {
+            events.push(PhysicsEvent::Removed {
+                handle: store.handle(i),
+                position: store.positions()[i],
+                cause: RemovalCause::Recycled,
+            });
+        });
+    }
+
+    /// Spawns refused because the particle budget was full
+    pub fn dropped_spawns(&self) -> u64 {
+        self.dropped_spawns
+    }
+
+    /// Old force particles removed early to make room for new ones
+    pub fn recycled_particles(&self) -> u64 {
+        self.recycled_particles
+    }
+
+    /// Energy, momentum and event counts as of the last `update`
+    pub fn stats(&self) -> PhysicsStats {
+        let mut kinetic_energy = 0.0;
+        let mut spring_potential_energy = 0.0;
+        let mut momentum = Vec3::new();
+        let mut max_displacement: f64 = 0.0;
+        let mut max_speed_squared: f64 = 0.0;
+
+        for (particle, state) in self.wall_particles.iter().zip(&self.wall_states) {
+            let speed_squared = particle.velocity.mag_squared();
+            max_speed_squared = max_speed_squared.max(speed_squared);
+            // Pinned particles never move and may have infinite mass
+            if !particle.is_pinned() {
+                kinetic_energy += 0.5 * particle.mass * speed_squared;
+                momentum.add(&(particle.velocity * particle.mass));
+            }
+            // Particles gliding back from a boundary are not held by the wall either
+            if state.is_attached() {
+                let displacement = particle.displacement().mag_squared();
+                max_displacement = max_displacement.max(displacement);
+                if !particle.is_pinned() {
+                    spring_potential_energy += 0.5 * particle.mass * self.config.gravity_stiffness * displacement;
+                }
+            }
+        }
+        let max_displacement = max_displacement.sqrt();
+
+        if self.config.wall_model == WallModel::Cloth {
+            for spring in self.cloth.springs() {
+                let stiffness = self.config.cloth.params(spring.kind).stiffness;
+                let length = (self.wall_particles[spring.b].position - self.wall_particles[spring.a].position).magnitude();
+                let stretch = length - spring.rest_length;
+                spring_potential_energy += 0.5 * stiffness * stretch * stretch;
+            }
+        }
+
+        for (velocity, &mass) in self.force_particles.velocities().iter().zip(self.force_particles.masses()) {
+            let speed_squared = velocity.mag_squared();
+            max_speed_squared = max_speed_squared.max(speed_squared);
+            kinetic_energy += 0.5 * mass * speed_squared;
+            momentum.add(&(*velocity * mass));
+        }
+
+        PhysicsStats {
+            time: self.sim_time,
+            wall_particles: self.wall_particles.len(),
+            force_particles: self.force_particles.len(),
+            kinetic_energy,
+            spring_potential_energy,
+            momentum,
+            max_displacement,
+            max_velocity: max_speed_squared.sqrt(),
+            counts: self.frame_counts,
+        }
+    }
+
+    /// Append a row of `stats` to a CSV file at `path` after every `update`, replacing any
+    /// file already there. Streaming stops with a warning if a write fails.
+    pub fn stream_stats_to(&mut self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        self.stats_csv = Some(StatsCsv::create(path)?);
+        Ok(())
+    }
+
+    /// Stop streaming stats, flushing what has been written
+    pub fn stop_stats_stream(&mut self) -> std::io::Result<()> {
+        match self.stats_csv.take() {
+            Some(mut csv) => csv.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Register a rule that updates particle attributes every step, after earlier rules
+    pub fn add_attribute_rule(&mut self, rule: Box<dyn AttributeRule>) {
+        self.attribute_rules.push(rule);
+    }
+
+    pub fn attribute_rules(&self) -> &[Box<dyn AttributeRule>] {
+        &self.attribute_rules
+    }
+
+    /// Register an emitter of force particles
+    pub fn add_emitter(&mut self, emitter: Emitter) -> EmitterId {
+        self.emitters.push(emitter);
+        self.emitters.len() - 1
+    }
+
+    pub fn emitter_mut(&mut self, id: EmitterId) -> Option<&mut Emitter> {
+        self.emitters.get_mut(id)
+    }
+
+    pub fn emitters(&self) -> &[Emitter] {
+        &self.emitters
+    }
+
+    /// Copies of every wall and force particle; prefer the slices for per-frame work
+    pub fn get_all_particles(&self) -> impl Iterator<Item = Particle> + '_ {
+        self.wall_particles.iter().cloned().chain(self.force_particles.iter())
+    }
+}
+
+/// Fraction of a lifetime of `lifetime_ms` used up at `now_ms`; zero for particles that never expire
+fn normalized_age(now_ms: u64, created_time: u64, lifetime_ms: u64) -> f64 {
+    if lifetime_ms == u64::MAX {
+        return 0.0;
+    }
+    if lifetime_ms == 0 {
+        return 1.0;
+    }
+    (now_ms.saturating_sub(created_time) as f64 / lifetime_ms as f64).min(1.0)
+}
+
+/// Force particles integrated per parallel task
+const FORCE_CHUNK: usize = 1024;
+
+/// How far in front of the wall plane particles may go, so rounding
+/// noise around the rest position does not send settled particles gliding back
+const WALL_PLANE_SLACK: f64 = 1e-3;
+
+/// Two distinct elements of a slice, mutably
+fn pair_mut<T>(items: &mut [T], a: usize, b: usize) -> (&mut T, &mut T) {
+    if a < b {
+        let (low, high) = items.split_at_mut(b);
+        (&mut low[a], &mut high[0])
+    } else {
+        let (low, high) = items.split_at_mut(a);
+        (&mut high[0], &mut low[b])
+    }
+}
+
+/// Advance force particles under gravity, the fields and their own damping
+#[allow(clippy::too_many_arguments)]
+fn integrate_forces(
+    integrator: &dyn Integrator,
+    fields: &[FieldSlot],
+    gravity: Vec3,
+    positions: &mut [Vec3],
+    velocities: &mut [Vec3],
+    damping: &[f64],
+    dt: f64,
+    scratch: &mut IntegratorScratch,
+) {
+    let mut acceleration = |positions: &[Vec3], velocities: &[Vec3], out: &mut [Vec3]| {
+        for i in 0..positions.len() {
+            let mut a = force_field::total_acceleration(fields, ParticleKind::Force, &positions[i], &velocities[i]);
+            a.add(&gravity);
+            a.add(&(velocities[i] * -damping[i]));
+            out[i] = a;
+        }
+    };
+    integrator.integrate(positions, velocities, dt, &mut acceleration, scratch);
+}
+
+/// Resolve `particle` against each collider in order; returns whether any touched
+fn resolve_collider_contacts(
+    colliders: &[(&dyn Collider, MaterialId)],
+    materials: &MaterialTable,
+    particle: &mut Particle,
+) -> bool {
+    let mut touched = false;
+    for &(collider, material) in colliders {
+        if let Some(contact) = collider.contact(&particle.position, particle.radius) {
+            let (restitution, friction) = materials.get(material).combine(&materials.get(particle.material));
+            let surface_velocity = collider.velocity_at(&particle.position);
+            particle.resolve_contact(&contact, &surface_velocity, restitution, friction);
+            touched = true;
+        }
+    }
+    touched
+}
+
+/// Outcome of every collision on one wall particle during a sub-step
+struct WallHits {
+    wall_idx: usize,
+    wall_particle: Particle,
+    forces: Vec<ForceHit>,
+    max_impulse: f64,
+}
+
+/// One force particle's hit on a wall particle, after resolution
+struct ForceHit {
+    force_idx: usize,
+    force_particle: Particle,
+    impulse: f64,
+    point: Vec3,
+}
+
+/// Everything needed to evaluate wall accelerations for a trial state
+struct WallForces<'a> {
+    particles: &'a [Particle],
+    grid: &'a WallGrid,
+    d: usize,
+    config: &'a PhysicsConfig,
+    cloth: &'a Cloth,
+    fields: &'a [FieldSlot],
+    states: &'a [WallState],
+    asleep: &'a [bool],
+    execution: Execution,
+}
+
+impl WallForces<'_> {
+    /// Accelerations of the wall particles at trial `positions` and `velocities`
+    fn evaluate(&self, positions: &[Vec3], velocities: &[Vec3], out: &mut [Vec3]) {
+        self.execution.for_each_mut(out, |index, acceleration| {
+            if self.asleep[index] {
+                *acceleration = Vec3::new();
+                return;
+            }
+            let particle = &self.particles[index];
+            *acceleration = if !self.states[index].is_loose() {
+                particle.gravity_acceleration(&positions[index], self.config.gravity_stiffness)
+            } else {
+                self.config.gravity
+            };
+            acceleration.add(&(velocities[index] * -particle.damping));
+            acceleration.add(&force_field::total_acceleration(self.fields, ParticleKind::Wall, &positions[index], &velocities[index]));
+        });
+
+        match self.config.wall_model {
+            WallModel::ModuleGravity => self.add_module_accelerations(positions, out),
+            WallModel::Cloth => self.cloth.accumulate_accelerations(
+                &self.config.cloth,
+                self.particles,
+                positions,
+                velocities,
+                out,
+                self.execution,
+            ),
+        }
+
+        self.execution.for_each_mut(out, |index, acceleration| {
+            if self.particles[index].is_pinned() || self.asleep[index] {
+                *acceleration = Vec3::new();
+            }
+        });
+    }
+
+    /// Module pull: the mean displacement of the neighbors within `d` cells relative to the particle
+    fn add_module_accelerations(&self, positions: &[Vec3], out: &mut [Vec3]) {
+        if self.d == 0 {
+            return;
+        }
+
+        let displacements = self
+            .execution
+            .map(self.particles.len(), |index| positions[index] - *self.particles[index].get_original_position());
+
+        self.execution.for_each_mut(out, |index, acceleration| {
+            // Loose particles neither pull nor are pulled by the module; sleeping ones stay put
+            if self.states[index].is_loose() || self.asleep[index] {
+                return;
+            }
+            let mut module_force = Vec3::new();
+            let mut count = 0;
+            for neighbor_idx in self
+                .grid
+                .neighbors_within(index, self.d)
+                .filter(|&neighbor_idx| !self.states[neighbor_idx].is_loose())
+            {
+                module_force.add(&(displacements[neighbor_idx] - displacements[index]));
+                count += 1;
+            }
+            if count > 0 {
+                module_force.mult(self.config.module_stiffness / count as f64);
+                acceleration.add(&module_force);
+            }
+        });
     }
 }
 
@@ -168,3 +1459,589 @@ impl Default for PhysicsSystem {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::boundary::Axis;
+    use crate::clock::ManualClock;
+    use crate::cloth::BreakCause;
+    use crate::emitter::{EmissionRate, EmitterConfig, EmitterShape, ValueRange};
+    use crate::wall_grid::WallOrientation;
+    use std::collections::HashSet;
+    use std::time::Duration;
+
+    const FRAME: f64 = 1.0 / 60.0;
+
+    /// Default configuration on a wall small enough to keep debug builds quick
+    fn test_config() -> PhysicsConfig {
+        PhysicsConfig {
+            wall_grid: WallGrid::new(16, 12, 10.0, Vec3::from_coords(-80.0, -160.0, 0.0), WallOrientation::XY),
+            ..PhysicsConfig::default()
+        }
+    }
+
+    /// Seeded system on a manual clock with a cone of force particles streaming into the wall
+    fn spray_scene(config: PhysicsConfig) -> (PhysicsSystem, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock::new());
+        let mut system = PhysicsSystem::with_clock(PhysicsConfig { seed: Some(7), ..config }, clock.clone());
+        let mut emitter = Emitter::new(EmitterConfig {
+            shape: EmitterShape::Cone { half_angle: 0.4 },
+            rate: EmissionRate::Continuous { per_second: 1200.0 },
+            lifetime: ValueRange::new(0.5, 1.5),
+            ..EmitterConfig::default()
+        });
+        emitter.set_source(Vec3::from_coords(0.0, -100.0, -60.0), Vec3::from_coords(0.0, 0.0, 50.0));
+        system.add_emitter(emitter);
+        (system, clock)
+    }
+
+    fn run(system: &mut PhysicsSystem, clock: &ManualClock, frames: usize) {
+        for _ in 0..frames {
+            clock.advance(Duration::from_secs_f64(FRAME));
+            system.update(FRAME);
+        }
+    }
+
+    /// Bit patterns of every wall and force particle position and velocity
+    fn snapshot(system: &PhysicsSystem) -> Vec<u64> {
+        let wall = system.wall_particles.iter().flat_map(|p| [p.position, p.velocity]);
+        let force = system.force_particles.positions().iter().chain(system.force_particles.velocities()).copied();
+        wall.chain(force).flat_map(|v| [v.x.to_bits(), v.y.to_bits(), v.z.to_bits()]).collect()
+    }
+
+    #[test]
+    fn broadphase_finds_the_same_contacts_as_brute_force() {
+        let config = PhysicsConfig {
+            consume_force_particles: false,
+            ..test_config()
+        };
+        let (mut broadphase, broadphase_clock) = spray_scene(config.clone());
+        let (mut brute_force, brute_force_clock) = spray_scene(PhysicsConfig {
+            brute_force_collisions: true,
+            ..config
+        });
+
+        let mut collisions = 0;
+        for _ in 0..60 {
+            run(&mut broadphase, &broadphase_clock, 1);
+            run(&mut brute_force, &brute_force_clock, 1);
+            assert_eq!(broadphase.stats().counts.collisions, brute_force.stats().counts.collisions);
+            collisions += broadphase.stats().counts.collisions;
+        }
+        assert!(collisions > 100, "only {} collisions", collisions);
+        assert_eq!(snapshot(&broadphase), snapshot(&brute_force));
+    }
+
+    #[test]
+    fn same_seed_gives_bit_identical_runs() {
+        // A small budget and short lifetimes exercise recycling and expiry as well as spawning
+        let config = PhysicsConfig {
+            particle_budget: 300,
+            ..test_config()
+        };
+        let (mut first, first_clock) = spray_scene(config.clone());
+        let (mut second, second_clock) = spray_scene(config.clone());
+        run(&mut first, &first_clock, 120);
+        // Wall-clock time between frames must not matter, only the simulated steps
+        for frame in 0..120 {
+            second_clock.advance(Duration::from_millis(frame % 7 * 10));
+            second.update(FRAME);
+        }
+        assert!(first.recycled_particles() > 0);
+        assert_eq!(snapshot(&first), snapshot(&second));
+
+        let (mut reseeded, reseeded_clock) = spray_scene(config);
+        reseeded.reseed(8);
+        run(&mut reseeded, &reseeded_clock, 120);
+        assert_ne!(snapshot(&first), snapshot(&reseeded));
+    }
+
+    #[test]
+    fn parallel_execution_matches_single_threaded_bitwise() {
+        // Enough threads to split the loops even on a single-core machine
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+        for wall_model in [WallModel::ModuleGravity, WallModel::Cloth] {
+            let snapshots = [Execution::SingleThreaded, Execution::Parallel].map(|execution| {
+                let (mut system, clock) = spray_scene(PhysicsConfig {
+                    execution,
+                    wall_model,
+                    consume_force_particles: false,
+                    // Wall loops long enough to be split across the pool
+                    wall_grid: WallGrid::new(20, 16, 10.0, Vec3::from_coords(-100.0, -180.0, 0.0), WallOrientation::XY),
+                    electrostatics: ElectrostaticsConfig {
+                        enabled: true,
+                        cutoff: 6.0,
+                        ..ElectrostaticsConfig::default()
+                    },
+                    ..PhysicsConfig::default()
+                });
+                system.configure_wall(|column, _, particle| particle.attributes.charge = column as f64 * 0.01);
+                let emitter = system.emitter_mut(0).unwrap();
+                emitter.config.charge = ValueRange::new(-1.0, 1.0);
+                emitter.config.rate = EmissionRate::Continuous { per_second: 4000.0 };
+                emitter.position.z = -25.0;
+                pool.install(|| run(&mut system, &clock, 20));
+                assert!(system.force_particles.len() > FORCE_CHUNK);
+                snapshot(&system)
+            });
+            assert!(snapshots[0] == snapshots[1], "{:?} diverged", wall_model);
+        }
+    }
+
+    #[test]
+    fn wall_plane_follows_the_grid() {
+        let grids = [
+            WallGrid::new(12, 8, 10.0, Vec3::from_coords(50.0, 30.0, -20.0), WallOrientation::XZ),
+            WallGrid::new(12, 8, 10.0, Vec3::from_coords(-40.0, -60.0, 25.0), WallOrientation::ZY),
+            WallGrid::new(12, 8, 10.0, Vec3::from_coords(0.0, 0.0, -75.0), WallOrientation::XY),
+        ];
+        for wall_grid in grids {
+            let clock = Arc::new(ManualClock::new());
+            let mut system = PhysicsSystem::with_clock(
+                PhysicsConfig {
+                    wall_grid: wall_grid.clone(),
+                    ..test_config()
+                },
+                clock.clone(),
+            );
+            run(&mut system, &clock, 120);
+            let orientation = wall_grid.orientation;
+            assert!(system.wall_states().iter().all(WallState::is_attached), "{:?} wall not at rest", orientation);
+            assert_eq!(system.sleeping_count(), wall_grid.len(), "{:?} wall not asleep", orientation);
+
+            // Flung out in front of the wall, a particle glides back
+            let (u, v) = orientation.axes();
+            let front = v.cross(&u);
+            let index = wall_grid.index(5, 4);
+            system.wall_particles[index].velocity = front * 600.0;
+            run(&mut system, &clock, 1);
+            assert!(
+                matches!(system.wall_states()[index], WallState::Returning { .. }),
+                "{:?} plane let the particle through",
+                orientation
+            );
+        }
+    }
+
+    #[test]
+    fn every_spawned_particle_is_removed_exactly_once() {
+        let (mut system, clock) = spray_scene(PhysicsConfig {
+            particle_budget: 500,
+            // Narrower than the spray cone, so its edges fly out of bounds
+            force_boundaries: vec![AxisBoundary::between(Axis::X, -15.0, 15.0, BoundaryMode::Kill)],
+            ..test_config()
+        });
+        // Some die before reaching the wall, the rest live long enough to fill the budget
+        system.emitter_mut(0).unwrap().config.lifetime = ValueRange::new(0.2, 1.5);
+
+        let mut live = HashSet::new();
+        let mut causes = HashSet::new();
+        for _ in 0..150 {
+            run(&mut system, &clock, 1);
+            for event in system.drain_events() {
+                match event {
+                    PhysicsEvent::Spawned { handle, .. } => assert!(live.insert(handle), "{:?} spawned twice", handle),
+                    PhysicsEvent::Removed { handle, cause, .. } => {
+                        assert!(live.remove(&handle), "{:?} removed without being live", handle);
+                        causes.insert(cause);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let remaining: HashSet<_> = (0..system.force_particles.len()).map(|i| system.force_particles.handle(i)).collect();
+        assert_eq!(live, remaining);
+        for cause in [RemovalCause::Expired, RemovalCause::Consumed, RemovalCause::Recycled, RemovalCause::OutOfBounds] {
+            assert!(causes.contains(&cause), "nothing was {:?}", cause);
+        }
+    }
+
+    #[test]
+    fn overflow_policies_respect_the_budget() {
+        let budget = 200;
+        for policy in [OverflowPolicy::DropNew, OverflowPolicy::ScaleRate, OverflowPolicy::RecycleOldest] {
+            let (mut system, clock) = spray_scene(PhysicsConfig {
+                particle_budget: budget,
+                overflow_policy: policy,
+                consume_force_particles: false,
+                ..test_config()
+            });
+            // A second emitter, so ScaleRate has output to share out
+            let mut second = system.emitters()[0].clone();
+            second.position.x += 30.0;
+            system.add_emitter(second);
+
+            let mut spawned = 0;
+            let mut dropped = 0;
+            let mut recycled = 0;
+            for _ in 0..40 {
+                run(&mut system, &clock, 1);
+                let stats = system.stats();
+                assert!(stats.force_particles <= budget, "{:?} went over budget", policy);
+                spawned += stats.counts.spawned;
+                dropped += stats.counts.dropped_spawns;
+                recycled += stats.counts.recycled;
+                let events = system.drain_events();
+                let recycled_events = events
+                    .iter()
+                    .filter(|e| matches!(e, PhysicsEvent::Removed { cause: RemovalCause::Recycled, .. }))
+                    .count();
+                assert_eq!(recycled_events as u64, stats.counts.recycled);
+            }
+            assert_eq!(dropped, system.dropped_spawns());
+            assert_eq!(recycled, system.recycled_particles());
+            assert!(spawned > budget as u64);
+
+            match policy {
+                OverflowPolicy::DropNew | OverflowPolicy::ScaleRate => {
+                    assert!(dropped > 0, "{:?} dropped nothing", policy);
+                    assert_eq!(recycled, 0);
+                }
+                OverflowPolicy::RecycleOldest => {
+                    assert_eq!(dropped, 0);
+                    assert!(recycled > 0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn scale_rate_shares_the_room_between_emitters() {
+        let (mut system, clock) = spray_scene(PhysicsConfig {
+            particle_budget: 100,
+            overflow_policy: OverflowPolicy::ScaleRate,
+            ..test_config()
+        });
+        let mut second = system.emitters()[0].clone();
+        second.position.x += 30.0;
+        system.add_emitter(second);
+        // 20 particles due from each emitter per frame
+        run(&mut system, &clock, 4);
+        system.drain_events();
+
+        // 20 places left for 40 due: each emitter gets half its share
+        system.force_particles.remove_indices(&mut (0..20).collect::<Vec<_>>());
+        run(&mut system, &clock, 1);
+        let mut per_emitter = [0; 2];
+        for event in system.drain_events() {
+            if let PhysicsEvent::Spawned { emitter, .. } = event {
+                per_emitter[emitter] += 1;
+            }
+        }
+        assert_eq!(per_emitter, [10, 10]);
+        assert_eq!(system.stats().counts.dropped_spawns, 20);
+    }
+
+    #[test]
+    fn recycling_removes_the_oldest_particles_first() {
+        let (mut system, clock) = spray_scene(PhysicsConfig {
+            particle_budget: 150,
+            consume_force_particles: false,
+            ..test_config()
+        });
+        // One particle the emitters know nothing about, older than any of theirs
+        let stray = system.force_particles.push(Particle::new(0.0, 0.0, -500.0));
+
+        let mut spawned_at = std::collections::HashMap::new();
+        let mut recycled_any = false;
+        for frame in 0..30 {
+            run(&mut system, &clock, 1);
+            let mut recycled = Vec::new();
+            for event in system.drain_events() {
+                match event {
+                    PhysicsEvent::Spawned { handle, .. } => {
+                        spawned_at.insert(handle, frame);
+                    }
+                    PhysicsEvent::Removed { handle, cause: RemovalCause::Recycled, .. } => recycled.push(handle),
+                    _ => {}
+                }
+            }
+            if recycled.is_empty() {
+                continue;
+            }
+            recycled_any = true;
+            assert_eq!(system.force_particles.index_of(stray), None, "the unqueued particle outlived newer ones");
+            let newest_recycled = recycled.iter().filter_map(|h| spawned_at.get(h)).max().unwrap();
+            let oldest_kept = (0..system.force_particles.len())
+                .map(|i| spawned_at[&system.force_particles.handle(i)])
+                .min()
+                .unwrap();
+            assert!(newest_recycled <= &oldest_kept);
+        }
+        assert!(recycled_any);
+    }
+
+    #[test]
+    fn emitters_initialize_and_attribute_their_particles() {
+        let (mut system, clock) = spray_scene(test_config());
+        let mut second = Emitter::new(EmitterConfig {
+            color: [0.0, 1.0, 0.0, 1.0],
+            temperature: ValueRange::constant(40.0),
+            charge: ValueRange::constant(-1.0),
+            user_data: 99,
+            ..EmitterConfig::default()
+        });
+        second.set_source(Vec3::from_coords(0.0, -100.0, -200.0), Vec3::from_coords(0.0, 0.0, 10.0));
+        let second_id = system.add_emitter(second);
+        run(&mut system, &clock, 10);
+
+        let attributes = system.force_particles.attributes();
+        assert!(attributes.iter().any(|a| a.emitter == Some(0)));
+        assert!(attributes.iter().any(|a| a.emitter == Some(second_id)));
+        for attributes in attributes.iter().filter(|a| a.emitter == Some(second_id)) {
+            assert_eq!(attributes.temperature, 40.0);
+            assert_eq!(attributes.charge, -1.0);
+            assert_eq!(attributes.user_data, 99);
+        }
+        assert!(system.wall_particles.iter().all(|p| p.attributes.emitter.is_none()));
+
+        run(&mut system, &clock, 1);
+        for event in system.drain_events() {
+            if let PhysicsEvent::Spawned { handle, emitter, .. } = event {
+                let index = system.force_particles.index_of(handle).unwrap();
+                assert_eq!(system.force_particles.attributes()[index].emitter, Some(emitter));
+            }
+        }
+    }
+
+    #[test]
+    fn strong_charges_wake_sleeping_wall_particles() {
+        let clock = Arc::new(ManualClock::new());
+        let mut system = PhysicsSystem::with_clock(test_config(), clock.clone());
+        run(&mut system, &clock, 120);
+        assert_eq!(system.sleeping_count(), system.wall_particles.len());
+
+        system.config.electrostatics.enabled = true;
+        system.configure_wall(|_, _, particle| particle.attributes.charge = 1.0);
+        let target = system.wall_grid().index(8, 6);
+        let rest = *system.wall_particles[target].get_original_position();
+        let mut weak = Particle::new(rest.x, rest.y, rest.z - 8.0);
+        weak.attributes.charge = 0.1;
+        system.force_particles.push(weak);
+        run(&mut system, &clock, 1);
+        assert!(system.is_asleep(target), "a weak pull woke the wall");
+        assert_eq!(system.wall_particles[target].velocity.magnitude(), 0.0);
+
+        system.force_particles.clear();
+        let mut strong = Particle::new(rest.x, rest.y, rest.z - 8.0);
+        strong.attributes.charge = 5.0;
+        system.force_particles.push(strong);
+        run(&mut system, &clock, 1);
+        assert!(!system.is_asleep(target));
+        assert!(system.drain_events().contains(&PhysicsEvent::Woke { wall_index: target }));
+    }
+
+    #[test]
+    fn loose_particles_let_their_neighbors_sleep() {
+        let clock = Arc::new(ManualClock::new());
+        let mut system = PhysicsSystem::with_clock(
+            PhysicsConfig {
+                detach: DetachConfig {
+                    displacement: 20.0,
+                    respawn_after: 100.0,
+                    ..DetachConfig::default()
+                },
+                ..test_config()
+            },
+            clock.clone(),
+        );
+        run(&mut system, &clock, 120);
+
+        // Knocked out through the back of the wall, away from the wall plane
+        let loose = system.wall_grid().index(8, 6);
+        system.wall_particles[loose].velocity = Vec3::from_coords(0.0, 0.0, 2400.0);
+        run(&mut system, &clock, 1);
+        assert!(system.wall_states()[loose].is_detached());
+
+        run(&mut system, &clock, 120);
+        let awake: Vec<usize> = (0..system.wall_particles.len()).filter(|&i| !system.is_asleep(i)).collect();
+        assert_eq!(awake, vec![loose]);
+
+        system.drain_events();
+        for _ in 0..60 {
+            run(&mut system, &clock, 1);
+            let woke = system.drain_events().into_iter().filter(|e| matches!(e, PhysicsEvent::Woke { .. })).count();
+            assert_eq!(woke, 0, "the loose particle keeps waking the wall");
+        }
+    }
+
+    #[test]
+    fn detached_particles_fall_and_respawn_after_the_delay() {
+        let detach = DetachConfig {
+            respawn_after: 1.0,
+            respawn_duration: 0.5,
+            ..DetachConfig::default()
+        };
+        let floor = -250.0;
+        let config = PhysicsConfig {
+            gravity: Vec3::from_coords(0.0, -400.0, 0.0),
+            wall_boundaries: vec![AxisBoundary::at_least(Axis::Y, floor, BoundaryMode::Clamp)],
+            ..test_config()
+        };
+        let by_displacement = PhysicsConfig {
+            detach: DetachConfig {
+                displacement: 20.0,
+                ..detach.clone()
+            },
+            ..config.clone()
+        };
+        let by_impact = PhysicsConfig {
+            detach: DetachConfig {
+                impact_energy: 1000.0,
+                ..detach
+            },
+            ..config
+        };
+
+        for (trigger, config) in [("displacement", by_displacement), ("impact", by_impact)] {
+            let clock = Arc::new(ManualClock::new());
+            let mut system = PhysicsSystem::with_clock(config, clock.clone());
+            let target = system.wall_grid().index(8, 6);
+            if trigger == "displacement" {
+                // Flung out in front of the wall plane
+                system.wall_particles[target].velocity = Vec3::from_coords(0.0, 0.0, -2400.0);
+            } else {
+                let particle = bullet(&system, 8, 6);
+                system.force_particles.push(particle);
+            }
+            run(&mut system, &clock, 1);
+            assert!(system.drain_events().contains(&PhysicsEvent::Detached { wall_index: target }), "{} did not detach", trigger);
+
+            // Loose for `respawn_after` from the step it broke off in
+            let mut height = 0.0;
+            while system.sim_time() < 1.0 - 1e-9 {
+                assert!(system.wall_states()[target].is_detached(), "{} particle returned early", trigger);
+                height = system.wall_particles[target].position.y;
+                run(&mut system, &clock, 1);
+            }
+            assert_eq!(height, floor, "{} particle did not land on the floor", trigger);
+
+            let mut reattached = false;
+            for _ in 0..60 {
+                run(&mut system, &clock, 1);
+                reattached |= system.drain_events().contains(&PhysicsEvent::Reattached { wall_index: target });
+            }
+            assert!(reattached, "{} particle never reattached", trigger);
+            assert!(system.wall_states()[target].is_attached());
+            assert_eq!(system.wall_particles[target].displacement().magnitude(), 0.0);
+        }
+    }
+
+    #[test]
+    fn moving_body_pushes_the_wall_along() {
+        use crate::body_collider::BodySegment;
+        use crate::input::Joint;
+
+        for moving in [false, true] {
+            let clock = Arc::new(ManualClock::new());
+            let mut system = PhysicsSystem::with_clock(test_config(), clock.clone());
+            system.set_body_colliders(BodyColliders::new(vec![BodySegment::new(Joint::ElbowRight, Joint::RightHand, 6.0)]));
+            run(&mut system, &clock, 120);
+
+            // A forearm held across the wall, just short of touching it
+            let target = system.wall_grid().index(8, 6);
+            let rest = *system.wall_particles[target].get_original_position();
+            let mut body = BodyJoints::default();
+            body.right_hand.is_tracked = true;
+            body.elbow_right = rest + Vec3::from_coords(-20.0, 0.0, -12.0);
+            body.right_hand.position = rest + Vec3::from_coords(20.0, 0.0, -12.0);
+            system.update_attachments(&body);
+
+            // Pressed 3 units into the wall over one frame, or placed there without moving
+            clock.advance(Duration::from_secs_f64(FRAME));
+            body.elbow_right.z += 3.0;
+            body.right_hand.position.z += 3.0;
+            if !moving {
+                system.update_attachments(&body);
+            }
+            system.update_attachments(&body);
+            system.update(FRAME);
+
+            let velocity = system.wall_particles[target].velocity;
+            if moving {
+                // At least the forearm's own 180 units/s, into the wall
+                assert!(velocity.z >= 3.0 / FRAME - 1e-9, "{:?}", velocity);
+            } else {
+                assert_eq!(velocity.z, 0.0);
+                assert!(system.wall_particles[target].displacement().z > 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn attached_fields_follow_the_hand() {
+        use crate::force_field::{Activation, Falloff, PointAttractor};
+        use crate::input::Joint;
+
+        let mut system = PhysicsSystem::with_config(test_config());
+        let mut attachment = JointAttachment::new(Joint::RightHand, Activation::HandOpen);
+        attachment.offset = Vec3::from_coords(0.0, 5.0, 0.0);
+        let field = PointAttractor::new(Vec3::new(), 30.0, 50.0, Falloff::Constant);
+        let id = system.add_attached_field(Box::new(field), attachment);
+        assert!(!system.fields()[id].enabled, "placed before any pose arrived");
+
+        let mut body = BodyJoints::default();
+        body.right_hand.position = Vec3::from_coords(10.0, 20.0, -30.0);
+        body.right_hand.is_tracked = true;
+        body.right_hand.is_open = true;
+        system.update_attachments(&body);
+        let slot = &system.fields()[id];
+        assert!(slot.enabled);
+        // The field now sits at the hand plus the offset
+        let below = Vec3::from_coords(10.0, 0.0, -30.0);
+        let pull = slot.field.acceleration(&below, &Vec3::new());
+        assert!((pull - Vec3::from_coords(0.0, 30.0, 0.0)).magnitude() < 1e-12, "{:?}", pull);
+
+        body.right_hand.is_open = false;
+        system.update_attachments(&body);
+        assert!(!system.fields()[id].enabled);
+    }
+
+    /// Heavy force particle about to hit the wall particle at `(column, row)` head on
+    fn bullet(system: &PhysicsSystem, column: usize, row: usize) -> Particle {
+        let target = system.wall_grid().position(column, row);
+        let mut particle = Particle::new(target.x, target.y, target.z - 8.0);
+        particle.set_mass(5.0);
+        particle.velocity = Vec3::from_coords(0.0, 0.0, 300.0);
+        particle
+    }
+
+    #[test]
+    fn hard_hit_tears_springs_until_the_wall_is_reset() {
+        let clock = Arc::new(ManualClock::new());
+        let mut system = PhysicsSystem::with_clock(
+            PhysicsConfig {
+                wall_model: WallModel::Cloth,
+                cloth: ClothConfig {
+                    tear_impulse: 50.0,
+                    ..ClothConfig::default()
+                },
+                ..test_config()
+            },
+            clock.clone(),
+        );
+        let target = system.wall_grid().index(8, 6);
+        let particle = bullet(&system, 8, 6);
+        system.force_particles.push(particle);
+
+        run(&mut system, &clock, 1);
+        assert!(system.cloth().broken_count() > 0);
+        let breaks = system.drain_spring_breaks();
+        assert_eq!(breaks.len(), system.cloth().broken_count());
+        assert!(breaks
+            .iter()
+            .all(|spring| spring.cause == BreakCause::Impact && (spring.a == target || spring.b == target)));
+
+        // Breaks nobody drained do not pile up across updates
+        let particle = bullet(&system, 3, 3);
+        system.force_particles.push(particle);
+        run(&mut system, &clock, 1);
+        run(&mut system, &clock, 1);
+        assert!(system.drain_spring_breaks().iter().all(|spring| spring.cause != BreakCause::Impact));
+
+        system.reset_wall();
+        assert_eq!(system.cloth().broken_count(), 0);
+    }
+}
@@ -1,6 +1,7 @@
+use crate::clock::{MonotonicClock, SharedClock};
 use crate::vector::Vec3;
 use std::collections::HashMap;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 pub struct KeyPoint {
@@ -24,17 +25,23 @@ pub struct PoseDetector {
     is_initialized: bool,
     is_camera_ready: bool,
     simulation_time: f64,
-    last_update: u64,
+    last_update: Option<u64>,
+    clock: SharedClock,
 }
 
 impl PoseDetector {
     pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
+        Self::with_clock(Arc::new(MonotonicClock::new()))
+    }
+
+    pub fn with_clock(clock: SharedClock) -> Result<Self, Box<dyn std::error::Error>> {
         Ok(Self {
             keypoints: HashMap::new(),
             is_initialized: false,
             is_camera_ready: false,
             simulation_time: 0.0,
-            last_update: 0,
+            last_update: None,
+            clock,
         })
     }
 
@@ -51,6 +58,8 @@ impl PoseDetector {
         self.keypoints.insert("Neck".to_string(), KeyPoint::new(0.0, -50.0, 0.9));
         self.keypoints.insert("RShoulder".to_string(), KeyPoint::new(30.0, -40.0, 0.9));
         self.keypoints.insert("LShoulder".to_string(), KeyPoint::new(-30.0, -40.0, 0.9));
+        self.keypoints.insert("RElbow".to_string(), KeyPoint::new(45.0, -10.0, 0.85));
+        self.keypoints.insert("LElbow".to_string(), KeyPoint::new(-45.0, -10.0, 0.85));
         self.keypoints.insert("RWrist".to_string(), KeyPoint::new(60.0, 20.0, 0.8));
         self.keypoints.insert("LWrist".to_string(), KeyPoint::new(-60.0, 20.0, 0.8));
         
@@ -62,16 +71,14 @@ impl PoseDetector {
             return Ok(());
         }
 
-        let current_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)?
-            .as_millis() as u64;
+        let current_time = self.clock.now_ms();
 
         // Update simulation time
-        if self.last_update > 0 {
-            let dt = (current_time - self.last_update) as f64 / 1000.0;
+        if let Some(last_update) = self.last_update {
+            let dt = current_time.saturating_sub(last_update) as f64 / 1000.0;
             self.simulation_time += dt;
         }
-        self.last_update = current_time;
+        self.last_update = Some(current_time);
 
         // Simulate dynamic hand movement
         let right_hand_x = 50.0 + 30.0 * (self.simulation_time * 0.5).sin();
@@ -86,6 +93,12 @@ impl PoseDetector {
         self.keypoints.insert("LWrist".to_string(), 
             KeyPoint::new(left_hand_x, left_hand_y, 0.85));
 
+        // Elbows trail halfway between the shoulders and the moving wrists
+        self.keypoints.insert("RElbow".to_string(),
+            KeyPoint::new((30.0 + right_hand_x) * 0.5, (-40.0 + right_hand_y) * 0.5, 0.85));
+        self.keypoints.insert("LElbow".to_string(),
+            KeyPoint::new((-30.0 + left_hand_x) * 0.5, (-40.0 + left_hand_y) * 0.5, 0.85));
+
         Ok(())
     }
 
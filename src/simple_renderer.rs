@@ -1,5 +1,6 @@
 // Simple renderer using basic shapes instead of complex wgpu setup
 use crate::particle::Particle;
+use crate::particle_store::ParticleStore;
 use winit::dpi::PhysicalSize;
 
 pub struct Renderer {
@@ -20,10 +21,11 @@ impl Renderer {
         self.height = new_size.height;
     }
 
-    pub fn render(&mut self, particles: &[Particle]) -> Result<(), String> {
+    pub fn render(&mut self, wall_particles: &[Particle], force_particles: &ParticleStore) -> Result<(), String> {
         // For now, just print particle count to demonstrate it's working
-        if particles.len() > 0 {
-            println!("Rendering {} particles", particles.len());
+        let count = wall_particles.len() + force_particles.len();
+        if count > 0 {
+            println!("Rendering {} particles", count);
         }
         Ok(())
     }
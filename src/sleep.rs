@@ -0,0 +1,93 @@
+/// When resting wall particles stop being simulated
+#[derive(Debug, Clone, PartialEq)]
+pub struct SleepConfig {
+    pub enabled: bool,
+    /// Distance from the rest position below which a particle counts as resting
+    pub displacement: f64,
+    /// Speed below which a particle counts as resting, in units/s
+    pub velocity: f64,
+    /// Consecutive resting sub-steps before a particle falls asleep
+    pub steps: u32,
+}
+
+impl Default for SleepConfig {
+    /// Sleep after a second at rest at 60 Hz
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            displacement: 0.05,
+            velocity: 0.05,
+            steps: 60,
+        }
+    }
+}
+
+/// Which wall particles are asleep, and how long the awake ones have been resting
+#[derive(Debug, Clone, Default)]
+pub struct SleepTracker {
+    asleep: Vec<bool>,
+    resting_steps: Vec<u32>,
+    sleeping: usize,
+}
+
+impl SleepTracker {
+    pub fn new(len: usize) -> Self {
+        Self {
+            asleep: vec![false; len],
+            resting_steps: vec![0; len],
+            sleeping: 0,
+        }
+    }
+
+    pub fn is_asleep(&self, index: usize) -> bool {
+        self.asleep[index]
+    }
+
+    /// Asleep flags by particle index
+    pub fn asleep(&self) -> &[bool] {
+        &self.asleep
+    }
+
+    pub fn sleeping_count(&self) -> usize {
+        self.sleeping
+    }
+
+    pub fn all_asleep(&self) -> bool {
+        self.sleeping == self.asleep.len()
+    }
+
+    /// Wake particle `index` and restart its resting count; returns true if it was asleep
+    pub fn wake(&mut self, index: usize) -> bool {
+        self.resting_steps[index] = 0;
+        if !self.asleep[index] {
+            return false;
+        }
+        self.asleep[index] = false;
+        self.sleeping -= 1;
+        true
+    }
+
+    pub fn wake_all(&mut self) {
+        if self.sleeping > 0 {
+            self.asleep.fill(false);
+            self.sleeping = 0;
+        }
+        self.resting_steps.fill(0);
+    }
+
+    /// Record one sub-step of an awake particle; returns true if it has now rested long
+    /// enough to fall asleep
+    pub fn observe(&mut self, index: usize, resting: bool, config: &SleepConfig) -> bool {
+        if !resting {
+            self.resting_steps[index] = 0;
+            return false;
+        }
+        self.resting_steps[index] = self.resting_steps[index].saturating_add(1);
+        if self.resting_steps[index] < config.steps || self.asleep[index] {
+            return false;
+        }
+        self.asleep[index] = true;
+        self.sleeping += 1;
+        true
+    }
+}
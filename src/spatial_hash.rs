@@ -0,0 +1,86 @@
+use crate::particle::Particle;
+use crate::vector::Vec3;
+use std::collections::HashMap;
+
+type CellKey = (i64, i64, i64);
+
+/// Uniform-grid spatial hash over a set of particles.
+/// Each particle is stored in the cell containing its center; the cell size is
+/// twice the largest radius, so a query only has to widen its search by that radius.
+#[derive(Debug, Clone)]
+pub struct SpatialHash {
+    cell_size: f64,
+    max_radius: f64,
+    cells: HashMap<CellKey, Vec<usize>>,
+}
+
+impl SpatialHash {
+    pub fn new() -> Self {
+        Self {
+            cell_size: 1.0,
+            max_radius: 0.0,
+            cells: HashMap::new(),
+        }
+    }
+
+    pub fn cell_size(&self) -> f64 {
+        self.cell_size
+    }
+
+    /// Rebuild the hash from scratch, indexing particles by their position in `particles`
+    pub fn rebuild(&mut self, particles: &[Particle]) {
+        self.cells.clear();
+        self.max_radius = particles.iter().map(|p| p.radius).fold(0.0, f64::max);
+        self.cell_size = (self.max_radius * 2.0).max(f64::EPSILON);
+
+        for (index, particle) in particles.iter().enumerate() {
+            let key = self.cell_of(&particle.position);
+            self.cells.entry(key).or_default().push(index);
+        }
+    }
+
+    /// Rebuild the hash over bare points with cells of `cell_size`, for range queries that
+    /// reach much further than the particles are wide
+    pub fn rebuild_points(&mut self, points: impl Iterator<Item = Vec3>, cell_size: f64) {
+        self.cells.clear();
+        self.max_radius = 0.0;
+        self.cell_size = cell_size.max(f64::EPSILON);
+
+        for (index, point) in points.enumerate() {
+            let key = self.cell_of(&point);
+            self.cells.entry(key).or_default().push(index);
+        }
+    }
+
+    /// Push into `out` the index of every particle whose sphere might overlap the sphere at
+    /// `point` with `radius`. Candidates still need an exact test.
+    pub fn query(&self, point: &Vec3, radius: f64, out: &mut Vec<usize>) {
+        let reach = radius + self.max_radius;
+        let min = self.cell_of(&Vec3::from_coords(point.x - reach, point.y - reach, point.z - reach));
+        let max = self.cell_of(&Vec3::from_coords(point.x + reach, point.y + reach, point.z + reach));
+
+        for cx in min.0..=max.0 {
+            for cy in min.1..=max.1 {
+                for cz in min.2..=max.2 {
+                    if let Some(indices) = self.cells.get(&(cx, cy, cz)) {
+                        out.extend_from_slice(indices);
+                    }
+                }
+            }
+        }
+    }
+
+    fn cell_of(&self, position: &Vec3) -> CellKey {
+        (
+            (position.x / self.cell_size).floor() as i64,
+            (position.y / self.cell_size).floor() as i64,
+            (position.z / self.cell_size).floor() as i64,
+        )
+    }
+}
+
+impl Default for SpatialHash {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -45,6 +45,15 @@ impl Vec3 {
         self.x * other.x + self.y * other.y + self.z * other.z
     }
 
+    /// Calculate cross product with another vector
+    pub fn cross(&self, other: &Vec3) -> Vec3 {
+        Vec3::from_coords(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
     /// Calculate magnitude squared
     pub fn mag_squared(&self) -> f64 {
         self.x * self.x + self.y * self.y + self.z * self.z
@@ -0,0 +1,198 @@
+use crate::boundary::{Axis, AxisBoundary, BoundaryMode};
+use crate::particle::Particle;
+use crate::vector::Vec3;
+
+/// Plane the wall grid is laid out in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WallOrientation {
+    /// Columns along +X, rows along +Y; the wall faces the user along Z
+    XY,
+    /// Columns along +X, rows along +Z; a floor or ceiling
+    XZ,
+    /// Columns along +Z, rows along +Y; a side wall
+    ZY,
+}
+
+impl WallOrientation {
+    /// Unit vectors along the column and row directions
+    pub fn axes(&self) -> (Vec3, Vec3) {
+        let x = Vec3::from_coords(1.0, 0.0, 0.0);
+        let y = Vec3::from_coords(0.0, 1.0, 0.0);
+        let z = Vec3::from_coords(0.0, 0.0, 1.0);
+        match self {
+            WallOrientation::XY => (x, y),
+            WallOrientation::XZ => (x, z),
+            WallOrientation::ZY => (z, y),
+        }
+    }
+}
+
+/// Rectangular grid of wall particles stored row by row
+#[derive(Debug, Clone, PartialEq)]
+pub struct WallGrid {
+    pub columns: usize,
+    pub rows: usize,
+    /// Distance between neighboring particle centers
+    pub spacing: f64,
+    /// Position of the particle at column 0, row 0
+    pub origin: Vec3,
+    pub orientation: WallOrientation,
+}
+
+impl WallGrid {
+    pub fn new(columns: usize, rows: usize, spacing: f64, origin: Vec3, orientation: WallOrientation) -> Self {
+        Self {
+            columns,
+            rows,
+            spacing,
+            origin,
+            orientation,
+        }
+    }
+
+    /// Number of particles in the grid
+    pub fn len(&self) -> usize {
+        self.columns * self.rows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Particle index of a grid cell
+    pub fn index(&self, column: usize, row: usize) -> usize {
+        row * self.columns + column
+    }
+
+    /// Grid cell of a particle index as `(column, row)`
+    pub fn coords(&self, index: usize) -> (usize, usize) {
+        (index % self.columns, index / self.columns)
+    }
+
+    /// Rest position of a grid cell
+    pub fn position(&self, column: usize, row: usize) -> Vec3 {
+        let (u, v) = self.orientation.axes();
+        self.origin + u * (column as f64 * self.spacing) + v * (row as f64 * self.spacing)
+    }
+
+    /// Index of the cell offset by `(dc, dr)` from `index`, if it lies inside the grid
+    pub fn neighbor(&self, index: usize, dc: i32, dr: i32) -> Option<usize> {
+        let (column, row) = self.coords(index);
+        let column = column as i64 + dc as i64;
+        let row = row as i64 + dr as i64;
+        if column < 0 || row < 0 || column >= self.columns as i64 || row >= self.rows as i64 {
+            None
+        } else {
+            Some(self.index(column as usize, row as usize))
+        }
+    }
+
+    /// Indices of all cells within `d` columns and rows of `index`, excluding `index` itself.
+    /// The window is clipped at the grid edges.
+    pub fn neighbors_within(&self, index: usize, d: usize) -> impl Iterator<Item = usize> + '_ {
+        let (column, row) = self.coords(index);
+        let columns = column.saturating_sub(d)..=(column + d).min(self.columns - 1);
+        let rows = row.saturating_sub(d)..=(row + d).min(self.rows - 1);
+        rows.flat_map(move |r| columns.clone().map(move |c| (c, r)))
+            .map(move |(c, r)| self.index(c, r))
+            .filter(move |&i| i != index)
+    }
+
+    /// Boundary stopping particles `slack` units in front of the wall plane. The front is
+    /// where the user stands, opposite the column × row normal: -Z for an XY wall.
+    pub fn front_boundary(&self, slack: f64, mode: BoundaryMode) -> AxisBoundary {
+        match self.orientation {
+            WallOrientation::XY => AxisBoundary::at_least(Axis::Z, self.origin.z - slack, mode),
+            WallOrientation::XZ => AxisBoundary::at_most(Axis::Y, self.origin.y + slack, mode),
+            WallOrientation::ZY => AxisBoundary::at_most(Axis::X, self.origin.x + slack, mode),
+        }
+    }
+
+    /// Create one particle per cell at its rest position
+    pub fn build_particles(&self, radius: f64) -> Vec<Particle> {
+        let mut particles = Vec::with_capacity(self.len());
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let position = self.position(column, row);
+                let mut particle = Particle::new(position.x, position.y, position.z);
+                particle.set_radius(radius);
+                particles.push(particle);
+            }
+        }
+        particles
+    }
+}
+
+impl Default for WallGrid {
+    /// 40 x 20 wall in the XY plane, matching the original installation layout
+    fn default() -> Self {
+        Self::new(40, 20, 10.0, Vec3::from_coords(-200.0, -200.0, 0.0), WallOrientation::XY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(orientation: WallOrientation) -> WallGrid {
+        WallGrid::new(5, 4, 10.0, Vec3::from_coords(3.0, -7.0, 11.0), orientation)
+    }
+
+    #[test]
+    fn neighbor_stays_inside_the_grid() {
+        let grid = grid(WallOrientation::XY);
+        let center = grid.index(2, 1);
+        assert_eq!(grid.neighbor(center, 1, 1), Some(grid.index(3, 2)));
+        assert_eq!(grid.neighbor(center, -2, -1), Some(grid.index(0, 0)));
+        assert_eq!(grid.neighbor(center, 0, 0), Some(center));
+        assert_eq!(grid.neighbor(center, -3, 0), None);
+        assert_eq!(grid.neighbor(center, 0, 3), None);
+
+        // Stepping off the end of a row does not wrap onto the next one
+        assert_eq!(grid.neighbor(grid.index(4, 1), 1, 0), None);
+        assert_eq!(grid.neighbor(grid.index(0, 2), -1, 0), None);
+        assert_eq!(grid.neighbor(grid.index(0, 0), 0, -1), None);
+    }
+
+    #[test]
+    fn neighbors_within_clips_at_the_edges() {
+        let grid = grid(WallOrientation::XY);
+        let collect = |index: usize, d: usize| {
+            let mut cells: Vec<(usize, usize)> = grid.neighbors_within(index, d).map(|i| grid.coords(i)).collect();
+            cells.sort_unstable();
+            cells
+        };
+
+        assert_eq!(collect(grid.index(0, 0), 1), vec![(0, 1), (1, 0), (1, 1)]);
+        assert_eq!(collect(grid.index(4, 3), 1), vec![(3, 2), (3, 3), (4, 2)]);
+        assert_eq!(collect(grid.index(2, 1), 1).len(), 8);
+        // Columns 0..=4 and rows 0..=3 around (2, 1), less the cell itself
+        assert_eq!(collect(grid.index(2, 1), 2).len(), 5 * 4 - 1);
+        assert_eq!(collect(grid.index(1, 2), 10).len(), grid.len() - 1);
+        assert!(collect(grid.index(1, 2), 0).is_empty());
+    }
+
+    #[test]
+    fn front_boundary_faces_away_from_the_wall() {
+        let slack = 0.5;
+        for orientation in [WallOrientation::XY, WallOrientation::XZ, WallOrientation::ZY] {
+            let grid = grid(orientation);
+            let boundary = grid.front_boundary(slack, BoundaryMode::Clamp);
+            let (u, v) = orientation.axes();
+            let front = v.cross(&u);
+            let rest = grid.position(3, 2);
+
+            assert!(!boundary.is_outside(&rest), "{:?}", orientation);
+            assert!(!boundary.is_outside(&(rest + front * (slack * 0.9))), "{:?}", orientation);
+            assert!(boundary.is_outside(&(rest + front * (slack * 1.1))), "{:?}", orientation);
+            assert!(!boundary.is_outside(&(rest - front * 100.0)), "{:?}", orientation);
+        }
+
+        let xz = grid(WallOrientation::XZ).front_boundary(slack, BoundaryMode::Clamp);
+        assert_eq!((xz.axis, xz.min, xz.max), (Axis::Y, None, Some(-6.5)));
+        let zy = grid(WallOrientation::ZY).front_boundary(slack, BoundaryMode::Clamp);
+        assert_eq!((zy.axis, zy.min, zy.max), (Axis::X, None, Some(3.5)));
+        let xy = grid(WallOrientation::XY).front_boundary(slack, BoundaryMode::Clamp);
+        assert_eq!((xy.axis, xy.min, xy.max), (Axis::Z, Some(10.5), None));
+    }
+}